@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Error, GroupSort, Prio};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// colors unless the output isn't a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+/// The settings from `config.toml` in the config directory, e.g.
+/// `~/.config/todo/config.toml`. Missing settings keep their defaults.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// the group of new tasks without `-g`
+    pub default_group: String,
+    /// the prio of new tasks without `-a`, `-b` or `-c`
+    pub default_prio: Prio,
+    /// how dates are shown, see the `chrono` strftime syntax
+    pub date_format: String,
+    pub color: ColorMode,
+    /// how the groups are ordered in listings
+    pub group_sort: GroupSort,
+    /// whether groups without matching tasks are left out of filtered listings
+    pub hide_empty_groups: bool,
+    /// whether task ids are shown and read in base 36; it is a setting rather
+    /// than a flag so that an id always refers to the same task
+    pub short_ids: bool,
+    /// e.g. `today = "add --due today"` makes `todo today call mom` add a task
+    pub aliases: BTreeMap<String, String>,
+    /// data files by profile name; relative paths start at the config directory
+    pub profiles: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            default_group: "Default".to_string(),
+            default_prio: Prio::B,
+            date_format: "%d.%m.%Y".to_string(),
+            color: ColorMode::Auto,
+            group_sort: GroupSort::Alpha,
+            hide_empty_groups: true,
+            short_ids: false,
+            aliases: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+fn get_config_dir() -> PathBuf {
+    directories::BaseDirs::new().config_dir().join("todo")
+}
+
+pub fn get_config_path() -> PathBuf {
+    get_config_dir().join("config.toml")
+}
+
+/// Reads the config file. The result is used with `load_config`.
+pub fn read_config() -> Result<Config, Error> {
+    let path = get_config_path();
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let text = std::fs::read_to_string(path).map_err(|_| Error::CannotReadConfigFile)?;
+    let config = toml::from_str::<Config>(&text)
+        .map_err(|e| Error::InvalidConfigFile(e.message().to_string()))?;
+    // an invalid format would only fail once a date is printed
+    if StrftimeItems::new(&config.date_format).any(|item| matches!(item, Item::Error)) {
+        return Err(Error::InvalidConfigFile(format!(
+            "invalid date_format `{}`",
+            config.date_format
+        )));
+    }
+    if config.default_group.is_empty() {
+        return Err(Error::InvalidConfigFile(
+            "default_group must not be empty".to_string(),
+        ));
+    }
+    Ok(config)
+}
+
+/// Makes the settings from the config file the effective configuration.
+/// Needs to be called before `config`. The settings given on the command line
+/// replace the ones from the file.
+pub fn load_config(mut config: Config, group_sort: Option<GroupSort>) {
+    if let Some(group_sort) = group_sort {
+        config.group_sort = group_sort;
+    }
+    let _ = CONFIG.set(config);
+}
+
+/// The effective configuration; the defaults if it wasn't loaded.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Expands a leading `~` and resolves relative paths against the config directory.
+pub fn resolve_config_path(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        return directories::BaseDirs::new().home_dir().join(rest);
+    }
+    get_config_dir().join(path)
+}
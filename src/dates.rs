@@ -0,0 +1,142 @@
+use crate::model::*;
+
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, Weekday};
+
+pub fn end_of_day(date: NaiveDate) -> Option<DateTime<Local>> {
+    date.and_hms_opt(23, 59, 59)?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+pub fn start_of_day(date: NaiveDate) -> Option<DateTime<Local>> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+pub fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parses offsets like `3d`, `2w` or `1m` relative to `today`.
+fn parse_offset(offset: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let unit = offset.chars().last()?;
+    let count: u32 = offset[..offset.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'd' => today.checked_add_days(Days::new(count.into())),
+        'w' => today.checked_add_days(Days::new(u64::from(count) * 7)),
+        'm' => today.checked_add_months(Months::new(count)),
+        _ => None,
+    }
+}
+
+/// Resolves a due date like `2026-11-03`, `tomorrow`, `fri`, `in 3d`,
+/// `next month` or `eod` to the end of the respective day. `none` yields `None`.
+pub fn resolve_due(spec: &str) -> Result<Option<DateTime<Local>>, Error> {
+    let spec_lower = spec.to_lowercase();
+    let today = Local::now().date_naive();
+    let words: Vec<&str> = spec_lower.split_whitespace().collect();
+    let date = match words.as_slice() {
+        ["none"] => return Ok(None),
+        ["today"] | ["eod"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["in", offset] => parse_offset(offset, today),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["next", "year"] => today.checked_add_months(Months::new(12)),
+        [word] => match parse_weekday(word) {
+            Some(weekday) => {
+                let from = today.weekday().num_days_from_monday();
+                let to = weekday.num_days_from_monday();
+                // the next such weekday, never today
+                let ahead = (to + 7 - from) % 7;
+                let ahead = if ahead == 0 { 7 } else { ahead };
+                today.checked_add_days(Days::new(ahead.into()))
+            }
+            None => NaiveDate::parse_from_str(word, "%Y-%m-%d").ok(),
+        },
+        _ => None,
+    };
+    date.and_then(end_of_day)
+        .map(Some)
+        .ok_or(Error::InvalidDueDate(spec.to_string()))
+}
+
+/// Parses a recurrence rule: `daily`, `weekly` (on today's weekday),
+/// `weekly:mon,thu`, `monthly:15` or `3d` (three days after completion).
+/// `none` yields `None`.
+pub fn resolve_recurrence(spec: &str) -> Result<Option<Recurrence>, Error> {
+    let invalid = || Error::InvalidRecurrence(spec.to_string());
+    let spec_lower = spec.to_lowercase();
+    let (kind, arg) = match spec_lower.split_once(':') {
+        Some((kind, arg)) => (kind, Some(arg)),
+        None => (spec_lower.as_str(), None),
+    };
+    let recurrence = match (kind, arg) {
+        ("none", None) => return Ok(None),
+        ("daily", None) => Recurrence::Daily,
+        ("weekly", None) => Recurrence::Weekly(vec![Local::now().weekday()]),
+        ("weekly", Some(days)) => {
+            let mut weekdays = days
+                .split(',')
+                .map(|day| parse_weekday(day).ok_or_else(invalid))
+                .collect::<Result<Vec<Weekday>, Error>>()?;
+            weekdays.sort_by_key(|day| day.num_days_from_monday());
+            weekdays.dedup();
+            Recurrence::Weekly(weekdays)
+        }
+        ("monthly", Some(day)) => match day.parse::<u32>() {
+            Ok(day @ 1..=31) => Recurrence::Monthly(day),
+            _ => return Err(invalid()),
+        },
+        (days, None) => match days.strip_suffix('d').map(str::parse::<u32>) {
+            Some(Ok(days)) if days > 0 => Recurrence::AfterCompletion(days),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    Ok(Some(recurrence))
+}
+
+/// The `day` of the month of `date`, or the last day if the month is shorter.
+fn clamped_day_of_month(date: NaiveDate, day: u32) -> Option<NaiveDate> {
+    (1..=day)
+        .rev()
+        .find_map(|day| date.with_day(day))
+}
+
+/// Computes the due date of the next occurrence of a recurring task that is
+/// completed today. Calendar based rules continue from the old due date but
+/// never produce a date in the past.
+pub fn next_occurrence(recurrence: &Recurrence, due: Option<DateTime<Local>>) -> Option<DateTime<Local>> {
+    let today = Local::now().date_naive();
+    let base = due.map_or(today, |due| due.date_naive().max(today));
+    let next = match recurrence {
+        Recurrence::Daily => base.succ_opt(),
+        Recurrence::Weekly(weekdays) => base
+            .iter_days()
+            .skip(1)
+            .take(7)
+            .find(|date| weekdays.contains(&date.weekday())),
+        Recurrence::Monthly(day) => {
+            let this_month = clamped_day_of_month(base, *day)?;
+            if this_month > base {
+                Some(this_month)
+            } else {
+                let next_month = base.with_day(1)?.checked_add_months(Months::new(1))?;
+                clamped_day_of_month(next_month, *day)
+            }
+        }
+        Recurrence::AfterCompletion(days) => today.checked_add_days(Days::new((*days).into())),
+    };
+    next.and_then(end_of_day)
+}
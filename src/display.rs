@@ -1,15 +1,16 @@
+use crate::config::config;
 use crate::model::*;
+use crate::search::SearchMatch;
 
 use chrono::{DateTime, Local};
 use colored::Colorize;
 use std::fmt::Display;
+use std::ops::Range;
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::TIDMissing => write!(f, "Need to specify the ID of the task."),
             Error::InvalidTID(tid) => write!(f, "Task ID `{}` is invalid.", tid),
-            Error::GroupMissing => write!(f, "Need to specify the group name after -g."),
             Error::InvalidArgument(arg) => write!(f, "Invalid argument: `{}`.", arg),
             Error::CannotReadDataFile => write!(f, "Cannot read data file."),
             Error::InvalidDataFile => write!(f, "Data file has the wrong format."),
@@ -18,13 +19,131 @@ impl Display for Error {
             Error::CannotWriteDataFile => write!(f, "Cannot write data file."),
             Error::NothingToUpdate => write!(f, "Nothing to update."),
             Error::InvalidGroup(group) => write!(f, "Invalid group name: `{}`.", group),
-            Error::TIDsMissing => write!(f, "The list of task IDs is missing."),
             Error::NothingToMove => write!(f, "Nothing to move."),
+            Error::CannotLockDataFile => write!(f, "Cannot create the lock file for the data file."),
+            Error::DataFileLocked => write!(
+                f,
+                "The data file is in use by another `todo` process. Try again in a moment."
+            ),
+            Error::CannotReadJournalFile => write!(f, "Cannot read journal file."),
+            Error::InvalidJournalFile => write!(
+                f,
+                "Journal file has the wrong format. Delete the `.journal` file next to the data file to start a new one."
+            ),
+            Error::NothingToUndo => write!(f, "Nothing to undo."),
+            Error::NothingToRedo => write!(f, "Nothing to redo."),
+            Error::InvalidArchiveIndex(index) => {
+                write!(f, "Archive index `{}` is invalid.", index)
+            }
+            Error::InvalidDays(days) => write!(f, "Invalid number of days: `{}`.", days),
+            Error::InvalidDueDate(due) => write!(
+                f,
+                "Invalid due date: `{}`. Try e.g. `2026-11-03`, `tomorrow`, `fri`, `in 3d`, `next month` or `eod`.",
+                due
+            ),
+            Error::InvalidRecurrence(recurrence) => write!(
+                f,
+                "Invalid recurrence: `{}`. Try `daily`, `weekly:mon,thu`, `monthly:15`, `3d` or `none`.",
+                recurrence
+            ),
+            Error::InvalidTag(tag) => write!(f, "Invalid tag: `{}`.", tag),
+            Error::CannotWriteNotesFile => write!(f, "Cannot write the temporary notes file."),
+            Error::EditorFailed(editor) => write!(f, "Editor `{}` failed.", editor),
+            Error::InvalidChecklistItem(index) => {
+                write!(f, "Checklist item `{}` is invalid.", index)
+            }
+            Error::DependencyCycle(path) => {
+                let path = path
+                    .iter()
+                    .map(|tid| tid_to_string(*tid))
+                    .collect::<Vec<String>>();
+                write!(f, "Dependency cycle: {}.", path.join(" -> "))
+            }
+            Error::Usage(message) => write!(f, "{}", message.trim_end()),
+            Error::InvalidSelector(selector) => write!(
+                f,
+                "Invalid selection: `{}`. Try e.g. `1,3-4,9`, `g:household`, `p:c`, `+tag` or `@overdue`.",
+                selector
+            ),
+            Error::NothingSelected => write!(f, "No tasks match the selection."),
+            Error::InvalidRegex(regex) => write!(f, "Invalid regular expression: `{}`.", regex),
+            Error::CannotOpenTerminal => write!(f, "Cannot open the terminal interface."),
+            Error::CannotReadImportFile(path) => write!(f, "Cannot read file `{}`.", path),
+            Error::InvalidImportFile(message) => write!(f, "The file has the wrong format: {}.", message),
+            Error::CannotReadConfigFile => write!(f, "Cannot read config file."),
+            Error::InvalidConfigFile(message) => {
+                write!(f, "Config file has the wrong format: {}", message.trim_end())
+            }
+            Error::UnknownProfile(profile) => write!(
+                f,
+                "Unknown profile `{}`. Profiles are defined in the [profiles] table of the config file.",
+                profile
+            ),
+            Error::InvalidDataFilePath(path) => {
+                write!(f, "`{}` is not a valid data file, the path needs to end in a file name.", path)
+            }
+            Error::InvalidData(problems) => {
+                write!(f, "The data file has {} problem(s):", problems.len())?;
+                for problem in problems.iter() {
+                    write!(f, "\n  - {problem}")?;
+                }
+                Ok(())
+            }
+            Error::NotConfirmed => write!(
+                f,
+                "Nothing was changed. Pass --yes to change many tasks without confirmation."
+            ),
         }
     }
 }
 
-fn format_date(date: &DateTime<Local>) -> String {
+impl Error {
+    /// A stable, machine-readable identifier of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidTID(_) => "invalid_tid",
+            Error::InvalidArgument(_) => "invalid_argument",
+            Error::CannotReadDataFile => "cannot_read_data_file",
+            Error::InvalidDataFile => "invalid_data_file",
+            Error::SerializationError => "serialization_error",
+            Error::CannotWriteOpenDataFile => "cannot_write_open_data_file",
+            Error::CannotWriteDataFile => "cannot_write_data_file",
+            Error::NothingToUpdate => "nothing_to_update",
+            Error::InvalidGroup(_) => "invalid_group",
+            Error::NothingToMove => "nothing_to_move",
+            Error::CannotLockDataFile => "cannot_lock_data_file",
+            Error::DataFileLocked => "data_file_locked",
+            Error::CannotReadJournalFile => "cannot_read_journal_file",
+            Error::InvalidJournalFile => "invalid_journal_file",
+            Error::NothingToUndo => "nothing_to_undo",
+            Error::NothingToRedo => "nothing_to_redo",
+            Error::InvalidArchiveIndex(_) => "invalid_archive_index",
+            Error::InvalidDays(_) => "invalid_days",
+            Error::InvalidDueDate(_) => "invalid_due_date",
+            Error::InvalidRecurrence(_) => "invalid_recurrence",
+            Error::InvalidTag(_) => "invalid_tag",
+            Error::CannotWriteNotesFile => "cannot_write_notes_file",
+            Error::EditorFailed(_) => "editor_failed",
+            Error::InvalidChecklistItem(_) => "invalid_checklist_item",
+            Error::DependencyCycle(_) => "dependency_cycle",
+            Error::Usage(_) => "usage",
+            Error::InvalidSelector(_) => "invalid_selector",
+            Error::NothingSelected => "nothing_selected",
+            Error::NotConfirmed => "not_confirmed",
+            Error::InvalidData(_) => "invalid_data",
+            Error::CannotReadConfigFile => "cannot_read_config_file",
+            Error::InvalidConfigFile(_) => "invalid_config_file",
+            Error::UnknownProfile(_) => "unknown_profile",
+            Error::InvalidDataFilePath(_) => "invalid_data_file_path",
+            Error::InvalidRegex(_) => "invalid_regex",
+            Error::CannotOpenTerminal => "cannot_open_terminal",
+            Error::CannotReadImportFile(_) => "cannot_read_import_file",
+            Error::InvalidImportFile(_) => "invalid_import_file",
+        }
+    }
+}
+
+pub fn format_date(date: &DateTime<Local>) -> String {
     let date = date.date_naive();
     let today = Local::now().date_naive();
     let diff = today - date;
@@ -33,39 +152,261 @@ fn format_date(date: &DateTime<Local>) -> String {
         1 => "yesterday".to_string(),
         2 => "2d ago".to_string(),
         3 => "3d ago".to_string(),
-        _ => format!("{}", date.format("%d.%m.%Y")),
+        _ => format!("{}", date.format(&config().date_format)),
+    }
+}
+
+pub fn prio_glyph(prio: Prio) -> &'static str {
+    match prio {
+        Prio::A => "★",
+        Prio::B => "•",
+        Prio::C => "-",
+    }
+}
+
+fn write_prio(f: &mut std::fmt::Formatter<'_>, prio: Prio) -> std::fmt::Result {
+    let glyph = prio_glyph(prio);
+    match prio {
+        Prio::A => write!(f, "{}", glyph.red()),
+        Prio::B => write!(f, "{glyph}"),
+        Prio::C => write!(f, "{}", glyph.dimmed()),
+    }
+}
+
+/// Tasks that are due within this many days are highlighted.
+const DUE_SOON_DAYS: i64 = 2;
+
+pub fn format_due(due: &DateTime<Local>) -> String {
+    let date = due.date_naive();
+    let today = Local::now().date_naive();
+    let diff = date - today;
+    match diff.num_days() {
+        -3 => "due 3d ago".to_string(),
+        -2 => "due 2d ago".to_string(),
+        -1 => "due yesterday".to_string(),
+        0 => "due today".to_string(),
+        1 => "due tomorrow".to_string(),
+        2 => "due in 2d".to_string(),
+        3 => "due in 3d".to_string(),
+        _ => format!("due {}", date.format(&config().date_format)),
+    }
+}
+
+/// How close a due date is, which decides its color.
+pub enum Urgency {
+    Overdue,
+    Soon,
+    Later,
+}
+
+pub fn due_urgency(due: &DateTime<Local>) -> Urgency {
+    let days_left = (due.date_naive() - Local::now().date_naive()).num_days();
+    if *due < Local::now() {
+        Urgency::Overdue
+    } else if days_left <= DUE_SOON_DAYS {
+        Urgency::Soon
+    } else {
+        Urgency::Later
+    }
+}
+
+fn write_due(f: &mut std::fmt::Formatter<'_>, due: &DateTime<Local>) -> std::fmt::Result {
+    let text = format_due(due);
+    match due_urgency(due) {
+        Urgency::Overdue => write!(f, "{}", text.red()),
+        Urgency::Soon => write!(f, "{}", text.yellow()),
+        Urgency::Later => write!(f, "{}", text.dimmed()),
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) => {
+                let weekdays = weekdays
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect::<Vec<String>>();
+                write!(f, "weekly:{}", weekdays.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{day}"),
+            Recurrence::AfterCompletion(days) => write!(f, "{days}d after completion"),
+        }
     }
 }
 
 impl Display for Task {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.prio {
-            Prio::A => write!(f, "{}", "★".red())?,
-            Prio::B => write!(f, "{}", "•")?,
-            Prio::C => write!(f, "{}", "-".dimmed())?,
-        }
-        write!(f, " {}", format!("[{}]", self.tid).dimmed())?;
-        write!(f, " {}", self.name)?;
-        write!(f, "  {}", format_date(&self.date).dimmed().italic())?;
+        write!(f, "{}", PrintTask::new(self, false))
+    }
+}
+
+/// Writes `text` with the given byte ranges highlighted.
+fn write_highlighted(
+    f: &mut std::fmt::Formatter<'_>,
+    text: &str,
+    spans: &[Range<usize>],
+    dimmed: bool,
+) -> std::fmt::Result {
+    let mut end = 0;
+    for span in spans.iter() {
+        let plain = &text[end..span.start];
+        write!(f, "{}", if dimmed { plain.dimmed() } else { plain.normal() })?;
+        write!(f, "{}", text[span.clone()].yellow().bold().underline())?;
+        end = span.end;
+    }
+    let plain = &text[end..];
+    write!(f, "{}", if dimmed { plain.dimmed() } else { plain.normal() })
+}
+
+/// A task in a listing; blocked tasks are dimmed.
+pub struct PrintTask<'a> {
+    task: &'a Task,
+    blocked: bool,
+    /// byte ranges of the name that are highlighted, e.g. search matches
+    highlights: &'a [Range<usize>],
+}
+
+impl<'a> PrintTask<'a> {
+    pub fn new(task: &'a Task, blocked: bool) -> Self {
+        PrintTask {
+            task,
+            blocked,
+            highlights: &[],
+        }
+    }
+
+    /// A task is blocked as long as one of its blockers isn't done.
+    pub fn in_model(task: &'a Task, model: &Model) -> Self {
+        let blocked = task
+            .blocked_by
+            .iter()
+            .any(|blocker| model.tasks.contains_key(blocker));
+        PrintTask::new(task, blocked)
+    }
+
+    pub fn highlighted(self, highlights: &'a [Range<usize>]) -> Self {
+        PrintTask { highlights, ..self }
+    }
+}
+
+impl<'a> Display for PrintTask<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let task = self.task;
+        write_prio(f, task.prio)?;
+        write!(f, " {}", format!("[{}]", tid_to_string(task.tid)).dimmed())?;
+        if self.blocked {
+            write!(f, " 🔒 ")?;
+        } else {
+            write!(f, " ")?;
+        }
+        write_highlighted(f, &task.name, self.highlights, self.blocked)?;
+        if !task.checklist.is_empty() {
+            let done = task.checklist.iter().filter(|item| item.done).count();
+            let progress = format!("[{}/{}]", done, task.checklist.len());
+            if done == task.checklist.len() {
+                write!(f, " {}", progress.green())?;
+            } else {
+                write!(f, " {}", progress.dimmed())?;
+            }
+        }
+        for tag in task.tags.iter() {
+            write!(f, " {}", format!("+{tag}").dimmed())?;
+        }
+        if task.recurrence.is_some() {
+            write!(f, " {}", "↻".dimmed())?;
+        }
+        if task.notes.is_some() {
+            write!(f, " {}", "✎".dimmed())?;
+        }
+        write!(f, "  {}", format_date(&task.date).dimmed().italic())?;
+        if let Some(due) = &task.due {
+            write!(f, "  ")?;
+            write_due(f, due)?;
+        }
+        Ok(())
+    }
+}
+
+/// All the details of a single task, including its notes.
+pub struct PrintTaskDetails<'a> {
+    task: &'a Task,
+}
+
+impl<'a> PrintTaskDetails<'a> {
+    pub fn new(task: &'a Task) -> Self {
+        PrintTaskDetails { task }
+    }
+}
+
+impl<'a> Display for PrintTaskDetails<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let task = self.task;
+        writeln!(f, "\n{task}\n")?;
+        writeln!(f, "  {}  {}", "group:  ".dimmed(), task.group)?;
+        writeln!(
+            f,
+            "  {}  {}",
+            "created:".dimmed(),
+            task.date.format(&format!("{} %H:%M", config().date_format))
+        )?;
+        if let Some(due) = &task.due {
+            writeln!(f, "  {}  {}", "due:    ".dimmed(), due.format(&config().date_format))?;
+        }
+        if let Some(recurrence) = &task.recurrence {
+            writeln!(f, "  {}  {}", "recurs: ".dimmed(), recurrence)?;
+        }
+        if !task.tags.is_empty() {
+            let tags = task
+                .tags
+                .iter()
+                .map(|tag| format!("+{tag}"))
+                .collect::<Vec<String>>();
+            writeln!(f, "  {}  {}", "tags:   ".dimmed(), tags.join(" "))?;
+        }
+        if !task.blocked_by.is_empty() {
+            let blockers = task
+                .blocked_by
+                .iter()
+                .map(|tid| tid_to_string(*tid))
+                .collect::<Vec<String>>();
+            writeln!(f, "  {}  {}", "blocked:".dimmed(), blockers.join(", "))?;
+        }
+        if !task.checklist.is_empty() {
+            writeln!(f)?;
+            for (i, item) in task.checklist.iter().enumerate() {
+                let check = if item.done { "[x]" } else { "[ ]" };
+                writeln!(f, "  {} {} {}", check, format!("{}.", i + 1).dimmed(), item.text)?;
+            }
+        }
+        if let Some(notes) = &task.notes {
+            writeln!(f)?;
+            for line in notes.lines() {
+                writeln!(f, "  {line}")?;
+            }
+        }
         Ok(())
     }
 }
 
 pub struct PrintGroup<'a> {
     name: &'a str,
-    tasks: Vec<&'a Task>,
+    tasks: Vec<PrintTask<'a>>,
 }
 
 impl<'a> PrintGroup<'a> {
-    pub fn new(group: &'a Group, model: &'a Model, prio: Option<Prio>) -> Self {
+    pub fn new(group: &'a Group, model: &'a Model, filter: &TaskFilter) -> Self {
         let mut tasks = group
             .tids
             .iter()
             .map(|tid| model.tasks.get(tid).unwrap())
             .collect::<Vec<&Task>>();
-        if let Some(prio) = prio {
-            tasks.retain(|t| t.prio == prio);
-        }
+        tasks.retain(|t| filter.matches(t));
+        let tasks = tasks
+            .into_iter()
+            .map(|task| PrintTask::in_model(task, model))
+            .collect();
         PrintGroup {
             name: &group.name,
             tasks,
@@ -76,7 +417,11 @@ impl<'a> PrintGroup<'a> {
 impl<'a> Display for PrintGroup<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.tasks.is_empty() {
-            return write!(f, "");
+            if config().hide_empty_groups {
+                return write!(f, "");
+            }
+            writeln!(f, "\n{}\n", self.name.bold())?;
+            return writeln!(f, "  {}", "(no matching todos)".dimmed());
         }
         writeln!(f, "\n{}\n", self.name.bold())?;
         for task in self.tasks.iter() {
@@ -86,34 +431,57 @@ impl<'a> Display for PrintGroup<'a> {
     }
 }
 
+/// The groups in the order in which they are listed, see `GroupSort`. Ties
+/// are broken alphabetically.
+pub fn ordered_groups(model: &Model) -> Vec<&Group> {
+    let mut groups = model.groups.values().collect::<Vec<&Group>>();
+    groups.sort_by_key(|group| group.name.to_lowercase());
+    match config().group_sort {
+        GroupSort::Alpha => {}
+        GroupSort::Urgent => groups.sort_by_key(|group| {
+            // the tasks of a group are sorted, so the first one is the most urgent
+            let task = group.tids.first().and_then(|tid| model.tasks.get(tid));
+            task.map(|task| (task.prio, task.due.is_none(), task.due))
+        }),
+        GroupSort::Size => groups.sort_by_key(|group| std::cmp::Reverse(group.tids.len())),
+        GroupSort::Recent => groups.sort_by_key(|group| std::cmp::Reverse(group.touched)),
+        GroupSort::Manual => groups.sort_by_key(|group| {
+            let position = model.group_order.iter().position(|name| *name == group.name);
+            position.unwrap_or(model.group_order.len())
+        }),
+    }
+    groups
+}
+
 pub struct PrintModel<'a> {
     groups: Vec<PrintGroup<'a>>,
-    prio: Option<Prio>,
+    filtered: bool,
 }
 
 impl<'a> PrintModel<'a> {
-    pub fn new(model: &'a Model, prio: Option<Prio>) -> Self {
-        let groups: Vec<PrintGroup<'a>> = model
-            .groups
-            .values()
-            .map(|g| PrintGroup::new(g, model, prio))
+    pub fn new(model: &'a Model, filter: &TaskFilter) -> Self {
+        let groups: Vec<PrintGroup<'a>> = ordered_groups(model)
+            .into_iter()
+            .map(|g| PrintGroup::new(g, model, filter))
             .collect();
-        // TODO: sort groups by something?
-        PrintModel { groups, prio }
+        PrintModel {
+            groups,
+            filtered: !filter.is_empty(),
+        }
     }
 }
 
 impl<'a> Display for PrintModel<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.groups.is_empty() && self.prio.is_none() {
+        if self.groups.is_empty() && !self.filtered {
             writeln!(f, "\nThere are no todos right now. Good job :)")?;
             return Ok(())
         }
-        // this case can only occur if the prio filters out all the tasks;
+        // this case can only occur if the filter removes all the tasks;
         // otherwise we delete groups without tasks immediately
         if self.groups.iter().all(|g| g.tasks.is_empty()) {
-            assert!(self.prio.is_some());
-            writeln!(f, "\nThere are no todos with the given priority.")?;
+            assert!(self.filtered);
+            writeln!(f, "\nThere are no todos matching the given priority and tags.")?;
             return Ok(())
         }
 
@@ -124,3 +492,100 @@ impl<'a> Display for PrintModel<'a> {
         Ok(())
     }
 }
+
+/// Search results, grouped by group like a listing.
+pub struct PrintSearch<'a> {
+    query: &'a str,
+    groups: Vec<(&'a str, Vec<(PrintTask<'a>, &'a SearchMatch<'a>)>)>,
+}
+
+impl<'a> PrintSearch<'a> {
+    pub fn new(query: &'a str, matches: &'a [SearchMatch<'a>], model: &'a Model) -> Self {
+        let mut groups: Vec<(&'a str, Vec<(PrintTask<'a>, &'a SearchMatch<'a>)>)> = Vec::new();
+        for m in matches.iter() {
+            let task = PrintTask::in_model(m.task, model).highlighted(&m.name_spans);
+            // the groups keep the order of their first match
+            match groups.iter_mut().find(|(name, _)| *name == m.task.group) {
+                Some((_, tasks)) => tasks.push((task, m)),
+                None => groups.push((&m.task.group, vec![(task, m)])),
+            }
+        }
+        PrintSearch { query, groups }
+    }
+}
+
+impl<'a> Display for PrintSearch<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.groups.is_empty() {
+            return writeln!(f, "\nThere are no todos matching `{}`.", self.query);
+        }
+        for (name, tasks) in self.groups.iter() {
+            writeln!(f, "\n{}\n", name.bold())?;
+            for (task, m) in tasks.iter() {
+                writeln!(f, "  {task}")?;
+                if let Some((line, spans)) = &m.notes_line {
+                    write!(f, "      {} ", "✎".dimmed())?;
+                    write_highlighted(f, line.trim_end(), spans, true)?;
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct PrintArchive<'a> {
+    /// archived tasks together with their index in the archive
+    entries: Vec<(usize, &'a ArchivedTask)>,
+    query: Option<&'a str>,
+}
+
+/// The archived tasks matching `query` together with their index in the
+/// archive, most recently completed first.
+pub fn archive_entries<'a>(model: &'a Model, query: Option<&str>) -> Vec<(usize, &'a ArchivedTask)> {
+    let mut entries: Vec<(usize, &'a ArchivedTask)> = model.archive.iter().enumerate().collect();
+    if let Some(query) = query {
+        let query = query.to_lowercase();
+        entries.retain(|(_, entry)| {
+            entry.task.name.to_lowercase().contains(&query)
+                || entry.task.group.to_lowercase().contains(&query)
+        });
+    }
+    entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.completed));
+    entries
+}
+
+impl<'a> PrintArchive<'a> {
+    pub fn new(model: &'a Model, query: Option<&'a str>) -> Self {
+        PrintArchive {
+            entries: archive_entries(model, query),
+            query,
+        }
+    }
+}
+
+impl<'a> Display for PrintArchive<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.entries.is_empty() {
+            match self.query {
+                Some(query) => writeln!(f, "\nNo archived tasks match `{query}`.")?,
+                None => writeln!(f, "\nThe archive is empty.")?,
+            }
+            return Ok(());
+        }
+        writeln!(f, "\n{}\n", "Archive".bold())?;
+        for (index, entry) in self.entries.iter() {
+            write!(f, "  ")?;
+            write_prio(f, entry.task.prio)?;
+            write!(f, " {}", format!("[{}]", index).dimmed())?;
+            write!(f, " {}", entry.task.name)?;
+            write!(f, "  {}", entry.task.group.dimmed())?;
+            writeln!(
+                f,
+                "  {}",
+                format!("done {}", format_date(&entry.completed)).dimmed().italic()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Local};
+
+use crate::display::ordered_groups;
+use crate::model::*;
+
+/// A task in an export; archived tasks carry their completion date.
+pub struct ExportTask<'a> {
+    pub task: &'a Task,
+    pub completed: Option<DateTime<Local>>,
+}
+
+pub struct ExportGroup<'a> {
+    pub name: &'a str,
+    pub tasks: Vec<ExportTask<'a>>,
+}
+
+/// Selects the tasks of an export like `todo ls` does, optionally together
+/// with the archived tasks. The tasks of a group are ordered by prio and
+/// creation date; groups without tasks are left out.
+pub fn export_groups<'a>(model: &'a Model, cmd: &ExportCommand) -> Result<Vec<ExportGroup<'a>>, Error> {
+    let mut names = ordered_groups(model)
+        .into_iter()
+        .map(|group| group.name.as_str())
+        .collect::<Vec<&str>>();
+    if cmd.include_done {
+        // groups that only have archived tasks left come last
+        let mut archived = model
+            .archive
+            .iter()
+            .map(|entry| entry.task.group.as_str())
+            .filter(|name| !model.groups.contains_key(*name))
+            .collect::<Vec<&str>>();
+        archived.sort_by_key(|name| name.to_lowercase());
+        archived.dedup();
+        names.extend(archived);
+    }
+    if let Some(group) = &cmd.group {
+        if !names.contains(&group.as_str()) {
+            return Err(Error::InvalidGroup(group.to_string()));
+        }
+        names.retain(|name| name == group);
+    }
+
+    let mut groups: Vec<ExportGroup<'a>> = Vec::new();
+    for name in names {
+        let mut tasks = model
+            .groups
+            .get(name)
+            .map(|group| group.tids.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|tid| ExportTask {
+                task: model.tasks.get(tid).unwrap(),
+                completed: None,
+            })
+            .collect::<Vec<ExportTask>>();
+        if cmd.include_done {
+            tasks.extend(
+                model
+                    .archive
+                    .iter()
+                    .filter(|entry| entry.task.group == name)
+                    .map(|entry| ExportTask {
+                        task: &entry.task,
+                        completed: Some(entry.completed),
+                    }),
+            );
+        }
+        tasks.retain(|entry| cmd.filter.matches(entry.task));
+        tasks.sort_by_key(|entry| (entry.task.prio, entry.task.date));
+        if !tasks.is_empty() {
+            groups.push(ExportGroup { name, tasks });
+        }
+    }
+    Ok(groups)
+}
+
+pub fn prio_letter(prio: Prio) -> char {
+    match prio {
+        Prio::A => 'A',
+        Prio::B => 'B',
+        Prio::C => 'C',
+    }
+}
+
+/// Renders a heading per group with a checklist of its tasks, e.g.
+/// ``- [ ] `A` call mom +phone, due 2026-10-23``.
+pub fn markdown(groups: &[ExportGroup]) -> String {
+    let mut sections: Vec<String> = Vec::new();
+    for group in groups.iter() {
+        let mut lines = vec![format!("# {}", group.name), String::new()];
+        for entry in group.tasks.iter() {
+            let task = entry.task;
+            let mut line = format!(
+                "- [{}] `{}` {}",
+                if entry.completed.is_some() { "x" } else { " " },
+                prio_letter(task.prio),
+                task.name
+            );
+            for tag in task.tags.iter() {
+                line.push_str(&format!(" +{tag}"));
+            }
+            if let Some(due) = &task.due {
+                line.push_str(&format!(", due {}", due.format("%Y-%m-%d")));
+            }
+            if let Some(completed) = &entry.completed {
+                line.push_str(&format!(", done {}", completed.format("%Y-%m-%d")));
+            }
+            lines.push(line);
+        }
+        sections.push(lines.join("\n") + "\n");
+    }
+    sections.join("\n")
+}
+
+/// Like `markdown`, for org-mode. The dates are org timestamps, so that due
+/// dates show up in the agenda.
+pub fn org(groups: &[ExportGroup]) -> String {
+    let mut sections: Vec<String> = Vec::new();
+    for group in groups.iter() {
+        let mut lines = vec![format!("* {}", group.name)];
+        for entry in group.tasks.iter() {
+            let task = entry.task;
+            let mut line = format!(
+                "- [{}] [#{}] {}",
+                if entry.completed.is_some() { "X" } else { " " },
+                prio_letter(task.prio),
+                task.name
+            );
+            for tag in task.tags.iter() {
+                line.push_str(&format!(" +{tag}"));
+            }
+            if let Some(due) = &task.due {
+                line.push_str(&format!(", due <{}>", due.format("%Y-%m-%d %a")));
+            }
+            if let Some(completed) = &entry.completed {
+                line.push_str(&format!(", done [{}]", completed.format("%Y-%m-%d %a")));
+            }
+            lines.push(line);
+        }
+        sections.push(lines.join("\n") + "\n");
+    }
+    sections.join("\n")
+}
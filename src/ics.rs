@@ -0,0 +1,421 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::dates::*;
+use crate::export::*;
+use crate::model::*;
+
+/// Lines longer than this many bytes are folded (RFC 5545, 3.1).
+const MAX_LINE_LENGTH: usize = 75;
+
+/// The key in `Task::extra` that keeps the UID of a task that was imported
+/// from another application, so that importing it again updates the task.
+pub const UID_KEY: &str = "ics_uid";
+
+/// A VTODO component. Properties that are missing are `None`.
+pub struct IcsTodo {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    /// the first category is the group, the others are tags
+    pub categories: Vec<String>,
+    pub prio: Option<Prio>,
+    pub created: Option<DateTime<Local>>,
+    pub due: Option<DateTime<Local>>,
+    pub completed: Option<DateTime<Local>>,
+    pub description: Option<String>,
+}
+
+/// The UID of a task that was exported from here.
+pub fn task_uid(task: &Task) -> String {
+    match task.extra.get(UID_KEY).and_then(|uid| uid.as_str()) {
+        Some(uid) => uid.to_string(),
+        None => format!("{}@todo", task.tid),
+    }
+}
+
+/// The id of the task that a UID like `12@todo` was exported from.
+pub fn uid_tid(uid: &str) -> Option<TID> {
+    uid.strip_suffix("@todo")?.parse().ok()
+}
+
+/// PRIORITY is 1 (highest) to 9 (lowest), 0 means undefined.
+fn priority(prio: Prio) -> u8 {
+    match prio {
+        Prio::A => 1,
+        Prio::B => 5,
+        Prio::C => 9,
+    }
+}
+
+fn parse_priority(value: &str) -> Option<Prio> {
+    match value.trim().parse::<u8>().ok()? {
+        1..=4 => Some(Prio::A),
+        5 => Some(Prio::B),
+        6..=9 => Some(Prio::C),
+        _ => None,
+    }
+}
+
+fn format_timestamp(date: &DateTime<Local>) -> String {
+    date.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Parses a DATE or DATE-TIME value. Dates without a time are taken as the
+/// end of the day for due dates and as its start otherwise. Times with a
+/// TZID are taken as local times.
+fn parse_timestamp(value: &str, end: bool) -> Option<DateTime<Local>> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&time).with_timezone(&Local));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return time.and_local_timezone(Local).earliest();
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    if end {
+        end_of_day(date)
+    } else {
+        start_of_day(date)
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Splits a list value like CATEGORIES at the commas that aren't escaped.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut item = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if c == ',' && !escaped {
+            items.push(unescape_text(&item));
+            item.clear();
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        item.push(c);
+    }
+    items.push(unescape_text(&item));
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Appends a content line, folded into lines of at most `MAX_LINE_LENGTH`
+/// bytes without splitting characters.
+fn push_line(ics: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            ics.push_str("\r\n ");
+            // the space counts towards the folded line
+            length = 1;
+        }
+        ics.push(c);
+        length += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn push_todo(ics: &mut String, entry: &ExportTask, now: &DateTime<Local>) {
+    let task = entry.task;
+    push_line(ics, "BEGIN:VTODO");
+    push_line(ics, &format!("UID:{}", escape_text(&task_uid(task))));
+    push_line(ics, &format!("DTSTAMP:{}", format_timestamp(now)));
+    push_line(ics, &format!("CREATED:{}", format_timestamp(&task.date)));
+    push_line(ics, &format!("SUMMARY:{}", escape_text(&task.name)));
+    let categories = std::iter::once(&task.group)
+        .chain(task.tags.iter())
+        .map(|category| escape_text(category))
+        .collect::<Vec<String>>();
+    push_line(ics, &format!("CATEGORIES:{}", categories.join(",")));
+    push_line(ics, &format!("PRIORITY:{}", priority(task.prio)));
+    if let Some(due) = &task.due {
+        push_line(ics, &format!("DUE:{}", format_timestamp(due)));
+    }
+    if let Some(notes) = &task.notes {
+        push_line(ics, &format!("DESCRIPTION:{}", escape_text(notes)));
+    }
+    match &entry.completed {
+        Some(completed) => {
+            push_line(ics, "STATUS:COMPLETED");
+            push_line(ics, &format!("COMPLETED:{}", format_timestamp(completed)));
+        }
+        None => push_line(ics, "STATUS:NEEDS-ACTION"),
+    }
+    push_line(ics, "END:VTODO");
+}
+
+/// Writes an iCalendar file (RFC 5545) with a VTODO per task.
+pub fn export(groups: &[ExportGroup]) -> String {
+    let now = Local::now();
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//todo//todo//EN");
+    for group in groups.iter() {
+        for entry in group.tasks.iter() {
+            push_todo(&mut ics, entry, &now);
+        }
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Splits a content line like `DUE;VALUE=DATE:20261023` into its name and
+/// its value; the parameters are dropped. Colons in quoted parameters don't
+/// count.
+fn split_content_line(line: &str) -> Option<(String, &str)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let name = head.split(';').next().unwrap();
+    Some((name.to_uppercase(), value))
+}
+
+fn invalid(line_number: usize, message: &str) -> Error {
+    Error::InvalidImportFile(format!("line {line_number}: {message}"))
+}
+
+/// Parses the VTODO components of an iCalendar file. Other components, like
+/// events, are skipped.
+pub fn import(text: &str) -> Result<Vec<IcsTodo>, Error> {
+    // unfold the lines, remembering where each of them started
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, last))) => last.push_str(continuation),
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+
+    let mut todos: Vec<IcsTodo> = Vec::new();
+    let mut todo: Option<IcsTodo> = None;
+    let mut calendar = false;
+    // components within a VTODO, like alarms, are skipped
+    let mut nested = 0;
+    for (number, line) in lines.iter() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (name, value) =
+            split_content_line(line).ok_or_else(|| invalid(*number, "expected `NAME:VALUE`"))?;
+        if todo.is_some() {
+            match name.as_str() {
+                "BEGIN" => {
+                    nested += 1;
+                    continue;
+                }
+                "END" if nested > 0 => {
+                    nested -= 1;
+                    continue;
+                }
+                _ if nested > 0 => continue,
+                _ => {}
+            }
+        }
+        match (name.as_str(), todo.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCALENDAR") => calendar = true,
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                todo = Some(IcsTodo {
+                    uid: None,
+                    summary: None,
+                    categories: Vec::new(),
+                    prio: None,
+                    created: None,
+                    due: None,
+                    completed: None,
+                    description: None,
+                });
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                todos.push(todo.take().unwrap());
+            }
+            ("UID", Some(todo)) => todo.uid = Some(unescape_text(value)),
+            ("SUMMARY", Some(todo)) => todo.summary = Some(unescape_text(value)),
+            ("CATEGORIES", Some(todo)) => todo.categories.extend(split_list(value)),
+            ("PRIORITY", Some(todo)) => todo.prio = parse_priority(value),
+            ("DESCRIPTION", Some(todo)) => todo.description = Some(unescape_text(value)),
+            ("CREATED" | "DUE" | "COMPLETED", Some(todo)) => {
+                let date = parse_timestamp(value, name == "DUE")
+                    .ok_or_else(|| invalid(*number, &format!("invalid date `{value}`")))?;
+                match name.as_str() {
+                    "CREATED" => todo.created = Some(date),
+                    "DUE" => todo.due = Some(date),
+                    _ => todo.completed = Some(date),
+                }
+            }
+            ("STATUS", Some(todo)) if value.eq_ignore_ascii_case("COMPLETED") => {
+                // some applications leave out the completion date
+                todo.completed = todo.completed.or(Some(Local::now()));
+            }
+            _ => {}
+        }
+    }
+    if !calendar {
+        return Err(invalid(1, "expected `BEGIN:VCALENDAR`"));
+    }
+    if todo.is_some() {
+        return Err(invalid(text.lines().count(), "expected `END:VTODO`"));
+    }
+    Ok(todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Map;
+
+    fn import_one(text: &str) -> IcsTodo {
+        let mut todos = import(text).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(todos.len(), 1);
+        todos.remove(0)
+    }
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn import_unfolds_lines() {
+        let todo = import_one(include_str!("../tests/fixtures/folded.ics"));
+        assert_eq!(todo.uid.as_deref(), Some("folded-1@example.com"));
+        assert_eq!(
+            todo.summary.as_deref(),
+            Some("Renew the passport before the trip and book an appointment at the town hall")
+        );
+        assert_eq!(
+            todo.description.as_deref(),
+            Some("Bring the old passport, two photos\nand the form")
+        );
+    }
+
+    #[test]
+    fn import_splits_escaped_categories() {
+        let todo = import_one(include_str!("../tests/fixtures/categories.ics"));
+        assert_eq!(
+            todo.categories,
+            vec!["home, garden", "outside;weekend", "urgent", "later"]
+        );
+        assert_eq!(todo.prio, Some(Prio::A));
+    }
+
+    #[test]
+    fn import_reads_due_dates_without_time() {
+        let todo = import_one(include_str!("../tests/fixtures/due-date.ics"));
+        let due = NaiveDate::from_ymd_opt(2026, 10, 23).unwrap();
+        assert_eq!(todo.due, end_of_day(due));
+        assert_eq!(todo.created, Some(local(2026, 10, 1, 8, 30)));
+        assert_eq!(todo.prio, Some(Prio::B));
+    }
+
+    #[test]
+    fn import_skips_alarms_and_events() {
+        let todo = import_one(include_str!("../tests/fixtures/alarm.ics"));
+        assert_eq!(todo.uid.as_deref(), Some("alarm-1@example.com"));
+        assert_eq!(todo.summary.as_deref(), Some("Call the plumber"));
+        assert_eq!(todo.description, None);
+        assert_eq!(todo.prio, Some(Prio::C));
+    }
+
+    #[test]
+    fn import_completes_tasks_without_completion_date() {
+        let todos = import(include_str!("../tests/fixtures/completed.ics")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(todos.len(), 3);
+        assert!(todos[0].completed.is_some());
+        let completed = Utc.with_ymd_and_hms(2026, 10, 15, 18, 0, 0).unwrap();
+        assert_eq!(todos[1].completed, Some(completed.with_timezone(&Local)));
+        assert_eq!(todos[2].completed, None);
+    }
+
+    #[test]
+    fn import_rejects_unterminated_todos() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Lost\r\nEND:VCALENDAR\r\n";
+        assert!(matches!(import(text), Err(Error::InvalidImportFile(_))));
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let task = Task {
+            tid: 12,
+            name: "Buy milk, eggs; and a very long list of other things that needs folding ✓".to_string(),
+            prio: Prio::A,
+            date: local(2026, 10, 1, 9, 15),
+            group: "Groceries, weekly".to_string(),
+            due: Some(local(2026, 10, 23, 18, 0)),
+            recurrence: None,
+            tags: vec!["errand".to_string()],
+            notes: Some("first line\nsecond line".to_string()),
+            checklist: Vec::new(),
+            blocked_by: Vec::new(),
+            extra: Map::new(),
+        };
+        let archived = Task {
+            tid: 13,
+            name: "Return the bottles".to_string(),
+            prio: Prio::C,
+            ..task.clone()
+        };
+        let groups = [ExportGroup {
+            name: &task.group,
+            tasks: vec![
+                ExportTask {
+                    task: &task,
+                    completed: None,
+                },
+                ExportTask {
+                    task: &archived,
+                    completed: Some(local(2026, 10, 2, 10, 0)),
+                },
+            ],
+        }];
+
+        let ics = export(&groups);
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_LENGTH));
+        let todos = import(&ics).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(todos.len(), 2);
+
+        let todo = &todos[0];
+        assert_eq!(todo.uid.as_deref().and_then(uid_tid), Some(12));
+        assert_eq!(todo.summary.as_deref(), Some(task.name.as_str()));
+        assert_eq!(todo.categories, vec!["Groceries, weekly", "errand"]);
+        assert_eq!(todo.prio, Some(Prio::A));
+        assert_eq!(todo.created, Some(task.date));
+        assert_eq!(todo.due, task.due);
+        assert_eq!(todo.description, task.notes);
+        assert_eq!(todo.completed, None);
+
+        let todo = &todos[1];
+        assert_eq!(todo.uid.as_deref().and_then(uid_tid), Some(13));
+        assert_eq!(todo.prio, Some(Prio::C));
+        assert_eq!(todo.completed, Some(local(2026, 10, 2, 10, 0)));
+    }
+}
@@ -0,0 +1,260 @@
+use serde_json::Value;
+
+use crate::model::*;
+
+/// Appends the fields that differ between two JSON values to `changes`;
+/// `None` is a missing field. Objects and arrays are compared field by field,
+/// so that a change only keeps the parts of the model that it touched.
+fn diff_values(path: &mut Vec<String>, before: Option<&Value>, after: Option<&Value>, changes: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Some(old), Some(new)) if old == new => {}
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let mut keys = old.keys().chain(new.keys()).collect::<Vec<&String>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push(key.clone());
+                diff_values(path, old.get(key), new.get(key), changes);
+                path.pop();
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                path.push(index.to_string());
+                diff_values(path, old.get(index), new.get(index), changes);
+                path.pop();
+            }
+        }
+        _ => changes.push(FieldChange {
+            path: path.clone(),
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+    }
+}
+
+/// The fields that differ between two JSON documents, in a stable order.
+fn diff_documents(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let mut changes: Vec<FieldChange> = Vec::new();
+    diff_values(&mut Vec::new(), Some(before), Some(after), &mut changes);
+    changes
+}
+
+/// The fields of the model that a mutation changed.
+pub fn diff_models(before: &Model, after: &Model) -> Result<Vec<FieldChange>, Error> {
+    let before = serde_json::to_value(before).map_err(|_| Error::SerializationError)?;
+    let after = serde_json::to_value(after).map_err(|_| Error::SerializationError)?;
+    Ok(diff_documents(&before, &after))
+}
+
+/// Sets the field at `path`; `None` removes it. Removing an array element
+/// drops the elements after it as well, which belong to the same change.
+/// Fields whose parent is gone are skipped.
+fn set_field(document: &mut Value, path: &[String], value: Option<&Value>) {
+    let Some((last, parents)) = path.split_last() else {
+        if let Some(value) = value {
+            *document = value.clone();
+        }
+        return;
+    };
+    let mut parent = document;
+    for key in parents {
+        let child = match parent {
+            Value::Object(fields) => fields.get_mut(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get_mut(index)),
+            _ => None,
+        };
+        match child {
+            Some(child) => parent = child,
+            None => return,
+        }
+    }
+    match parent {
+        Value::Object(fields) => match value {
+            Some(value) => {
+                fields.insert(last.clone(), value.clone());
+            }
+            None => {
+                fields.remove(last);
+            }
+        },
+        Value::Array(items) => {
+            let Ok(index) = last.parse::<usize>() else {
+                return;
+            };
+            match value {
+                Some(value) if index < items.len() => items[index] = value.clone(),
+                Some(value) => items.push(value.clone()),
+                None => items.truncate(index),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies either the values before (`revert`) or after the changes. The
+/// changes are applied in their order, which keeps array elements in place in
+/// both directions.
+fn apply_to_document(document: &mut Value, changes: &[FieldChange], revert: bool) {
+    for change in changes.iter() {
+        let value = if revert { &change.before } else { &change.after };
+        set_field(document, &change.path, value.as_ref());
+    }
+}
+
+fn apply_changes(model: &Model, changes: &[FieldChange], revert: bool) -> Result<Model, Error> {
+    let mut document = serde_json::to_value(model).map_err(|_| Error::SerializationError)?;
+    apply_to_document(&mut document, changes, revert);
+    serde_json::from_value::<Model>(document).map_err(|_| Error::InvalidJournalFile)
+}
+
+/// Undoes a journal entry on the current model.
+pub fn revert_changes(model: &Model, changes: &[FieldChange]) -> Result<Model, Error> {
+    apply_changes(model, changes, true)
+}
+
+/// Redoes a journal entry on the current model.
+pub fn replay_changes(model: &Model, changes: &[FieldChange]) -> Result<Model, Error> {
+    apply_changes(model, changes, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use serde_json::{json, Map};
+
+    /// Checks that the changes turn `before` into `after` and back.
+    fn assert_round_trip(before: Value, after: Value) -> Vec<FieldChange> {
+        let changes = diff_documents(&before, &after);
+        let mut document = before.clone();
+        apply_to_document(&mut document, &changes, false);
+        assert_eq!(document, after);
+        apply_to_document(&mut document, &changes, true);
+        assert_eq!(document, before);
+        changes
+    }
+
+    fn paths(changes: &[FieldChange]) -> Vec<String> {
+        changes.iter().map(|change| change.path.join("/")).collect()
+    }
+
+    #[test]
+    fn diff_keeps_only_changed_fields() {
+        let changes = assert_round_trip(
+            json!({ "a": 1, "b": { "c": "x", "d": [1, 2] }, "gone": true }),
+            json!({ "a": 1, "b": { "c": "y", "d": [1, 2] }, "new": null }),
+        );
+        assert_eq!(paths(&changes), vec!["b/c", "gone", "new"]);
+        assert_eq!(changes[1].after, None);
+        assert_eq!(changes[2].before, None);
+        assert_eq!(changes[2].after, Some(Value::Null));
+    }
+
+    #[test]
+    fn diff_of_equal_documents_is_empty() {
+        let document = json!({ "a": [1, { "b": 2 }] });
+        assert!(diff_documents(&document, &document).is_empty());
+    }
+
+    #[test]
+    fn arrays_that_grow() {
+        let changes = assert_round_trip(json!({ "a": [1, 2] }), json!({ "a": [1, 2, 3, 4] }));
+        assert_eq!(paths(&changes), vec!["a/2", "a/3"]);
+    }
+
+    #[test]
+    fn arrays_that_shrink() {
+        let changes = assert_round_trip(json!({ "a": [1, 2, 3, 4] }), json!({ "a": [1, 4] }));
+        assert_eq!(paths(&changes), vec!["a/1", "a/2", "a/3"]);
+        assert_round_trip(json!([[1, 2], [3]]), json!([[1], [3, 4, 5]]));
+        assert_round_trip(json!({ "a": [1, 2] }), json!({ "a": [] }));
+    }
+
+    #[test]
+    fn fields_that_change_their_type() {
+        assert_round_trip(json!({ "a": [1, 2] }), json!({ "a": { "b": 1 } }));
+        assert_round_trip(json!({ "a": null }), json!({ "a": [1] }));
+    }
+
+    #[test]
+    fn changes_of_missing_parents_are_skipped() {
+        let changes = diff_documents(&json!({ "a": { "b": 1 } }), &json!({ "a": { "b": 2 } }));
+        let mut document = json!({ "c": 3 });
+        apply_to_document(&mut document, &changes, false);
+        assert_eq!(document, json!({ "c": 3 }));
+    }
+
+    fn task(tid: TID, name: &str) -> Task {
+        Task {
+            tid,
+            name: name.to_string(),
+            prio: Prio::B,
+            date: Local.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap(),
+            group: "home".to_string(),
+            due: None,
+            recurrence: None,
+            tags: Vec::new(),
+            notes: None,
+            checklist: Vec::new(),
+            blocked_by: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+
+    fn model(tasks: &[Task], archive: &[Task]) -> Model {
+        let mut model = Model::default();
+        for task in tasks.iter() {
+            model.tasks.insert(task.tid, task.clone());
+        }
+        model.groups.insert(
+            "home".to_string(),
+            Group {
+                name: "home".to_string(),
+                tids: tasks.iter().map(|task| task.tid).collect(),
+                touched: None,
+                extra: Map::new(),
+            },
+        );
+        model.archive = archive
+            .iter()
+            .map(|task| ArchivedTask {
+                task: task.clone(),
+                completed: Local.with_ymd_and_hms(2026, 10, 2, 9, 0, 0).unwrap(),
+                extra: Map::new(),
+            })
+            .collect();
+        model.next_tid = 3;
+        model
+    }
+
+    fn to_json(model: &Model) -> Value {
+        serde_json::to_value(model).unwrap()
+    }
+
+    #[test]
+    fn undo_and_redo_a_completion() {
+        let before = model(&[task(0, "dishes"), task(1, "laundry"), task(2, "trash")], &[]);
+        let after = model(&[task(0, "dishes"), task(2, "trash")], &[task(1, "laundry")]);
+        let changes = diff_models(&before, &after).unwrap_or_else(|e| panic!("{e}"));
+        assert!(paths(&changes).contains(&"tasks/1".to_string()));
+
+        let reverted = revert_changes(&after, &changes).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(to_json(&reverted), to_json(&before));
+        let replayed = replay_changes(&reverted, &changes).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(to_json(&replayed), to_json(&after));
+    }
+
+    #[test]
+    fn undo_keeps_later_unrelated_changes() {
+        let before = model(&[task(0, "dishes")], &[]);
+        let after = model(&[task(0, "wash the dishes")], &[]);
+        let changes = diff_models(&before, &after).unwrap_or_else(|e| panic!("{e}"));
+
+        let mut later = after.clone();
+        later.group_order = vec!["home".to_string()];
+        let reverted = revert_changes(&later, &changes).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(reverted.tasks[&0].name, "dishes");
+        assert_eq!(reverted.group_order, vec!["home".to_string()]);
+    }
+}
@@ -2,24 +2,49 @@ mod model;
 mod parse;
 mod processing;
 mod storage;
+mod journal;
 mod display;
+mod dates;
+mod output;
+mod config;
+mod search;
+mod tui;
+mod todotxt;
+mod export;
+mod ics;
+mod spreadsheet;
 
 use std::process::exit;
 
-use model::Error;
-use parse::parse;
+use model::{set_short_ids, Error};
+use output::Output;
+use parse::{expand_alias, parse, parse_global_options};
+use config::{config, load_config, read_config};
 use processing::process_command;
+use storage::select_data_file;
 
-fn _main() -> Result<(), Error> {
-    let args: Vec<String> = std::env::args().collect();
+fn _main(args: &[String], out: &mut Output) -> Result<(), Error> {
+    let file_config = read_config()?;
+    // aliases may contain global flags like `--profile`
+    let args = expand_alias(args, &file_config.aliases);
+    let options = parse_global_options(&args);
+    *out = Output::new(&options);
+    load_config(file_config, options.sort);
+    set_short_ids(config().short_ids);
+    out.set_color_mode(config().color);
     let command = parse(&args)?;
-    process_command(command)
+    select_data_file(&options)?;
+    process_command(command, out)
 }
 
 fn main() {
-    let result = _main();
+    let args: Vec<String> = std::env::args().collect();
+    // until the aliases are expanded, errors follow the flags given directly
+    let mut out = Output::new(&parse_global_options(&args));
+    let result = _main(&args, &mut out);
     if let Err(e) = result {
-        eprintln!("{e}");
+        out.error(&e);
         exit(1);
     }
+    out.finish();
 }
@@ -1,84 +1,437 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Weekday};
 
+#[allow(clippy::upper_case_acronyms)]
 pub type TID = u32;
 
+/// Whether task ids are shown and read in base 36 (`short_ids` in the config
+/// file), which keeps them short even though ids are never reused.
+static SHORT_IDS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_short_ids(enabled: bool) {
+    SHORT_IDS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn tid_to_string(tid: TID) -> String {
+    if !SHORT_IDS.load(Ordering::Relaxed) {
+        return tid.to_string();
+    }
+    let mut digits: Vec<char> = Vec::new();
+    let mut rest = tid;
+    loop {
+        digits.push(std::char::from_digit(rest % 36, 36).unwrap());
+        rest /= 36;
+        if rest == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+pub fn tid_from_str(tid: &str) -> Option<TID> {
+    let radix = if SHORT_IDS.load(Ordering::Relaxed) { 36 } else { 10 };
+    TID::from_str_radix(tid, radix).ok()
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub enum Prio {
+    #[serde(alias = "a")]
     A,
+    #[serde(alias = "b")]
     B,
+    #[serde(alias = "c")]
     C,
 }
 
+/// How a task comes back after it was completed.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Recurrence {
+    Daily,
+    Weekly(Vec<Weekday>),
+    /// on the given day of the month
+    Monthly(u32),
+    /// the given number of days after the task was completed
+    AfterCompletion(u32),
+}
+
+/// How the groups are ordered in listings.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupSort {
+    /// alphabetically by name
+    Alpha,
+    /// by the most urgent task, like the tasks within a group
+    Urgent,
+    /// the groups with the most tasks first
+    Size,
+    /// the most recently touched groups first
+    Recent,
+    /// in the order set with `todo ordergroups`; other groups follow alphabetically
+    Manual,
+}
+
+/// Flags that apply to every command.
+#[derive(Default)]
+pub struct GlobalOptions {
+    /// print a JSON object instead of the colored output
+    pub json: bool,
+    /// list tasks without grouping them (JSON only)
+    pub flat: bool,
+    /// don't ask before changing many tasks at once
+    pub yes: bool,
+    /// path of the data file
+    pub file: Option<String>,
+    /// name of a profile from the config file, which selects the data file
+    pub profile: Option<String>,
+    /// overrides `group_sort` from the config file
+    pub sort: Option<GroupSort>,
+}
+
 pub struct NewCommand {
     pub task: String,
     pub group: String,
     pub prio: Prio,
+    pub due: Option<DateTime<Local>>,
+    pub recurrence: Option<Recurrence>,
+    pub tags: Vec<String>,
+    pub items: Vec<String>,
+    pub blocked_by: Vec<TID>,
+}
+
+/// Restricts which tasks are listed.
+#[derive(Default)]
+pub struct TaskFilter {
+    pub prio: Option<Prio>,
+    /// the task needs to have all of these tags
+    pub tags: Vec<String>,
+    /// the task must not have any of these tags
+    pub excluded_tags: Vec<String>,
+}
+
+impl TaskFilter {
+    pub fn is_empty(&self) -> bool {
+        self.prio.is_none() && self.tags.is_empty() && self.excluded_tags.is_empty()
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.prio.is_none_or(|prio| task.prio == prio)
+            && self.tags.iter().all(|tag| task.tags.contains(tag))
+            && !self.excluded_tags.iter().any(|tag| task.tags.contains(tag))
+    }
+}
+
+/// One part of a task selection such as `1,3-4 g:household @overdue`.
+#[allow(clippy::upper_case_acronyms)]
+pub enum Selector {
+    TID(TID),
+    /// an inclusive range of task ids
+    Range(TID, TID),
+    Group(String),
+    Prio(Prio),
+    Tag(String),
+    Overdue,
+}
+
+impl Selector {
+    fn is_id(&self) -> bool {
+        matches!(self, Selector::TID(_) | Selector::Range(_, _))
+    }
+
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Selector::TID(tid) => task.tid == *tid,
+            Selector::Range(from, to) => (*from..=*to).contains(&task.tid),
+            Selector::Group(group) => task.group == *group,
+            Selector::Prio(prio) => task.prio == *prio,
+            Selector::Tag(tag) => task.tags.contains(tag),
+            Selector::Overdue => task.due.is_some_and(|due| due < Local::now()),
+        }
+    }
+}
+
+/// The tasks a command applies to: the listed ids (or all tasks if there are
+/// none) that match every filter.
+pub struct Selection {
+    pub selectors: Vec<Selector>,
+}
+
+impl Selection {
+    /// Whether every task was listed individually by its id.
+    pub fn is_explicit(&self) -> bool {
+        self.selectors
+            .iter()
+            .all(|selector| matches!(selector, Selector::TID(_)))
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        let (ids, filters): (Vec<&Selector>, Vec<&Selector>) =
+            self.selectors.iter().partition(|selector| selector.is_id());
+        (ids.is_empty() || ids.iter().any(|selector| selector.matches(task)))
+            && filters.iter().all(|selector| selector.matches(task))
+    }
 }
 
 pub struct ListCommand {
     pub group: Option<String>,
-    pub prio: Option<Prio>,
+    pub filter: TaskFilter,
 }
 
 pub struct DoneCommand {
-    pub tids: Vec<TID>,
+    pub selection: Selection,
 }
 
 pub struct UpdateCommand {
-    pub tid: TID,
+    pub selection: Selection,
     pub group: Option<String>,
     pub prio: Option<Prio>,
     pub task: Option<String>,
+    /// `Some(None)` removes the due date
+    pub due: Option<Option<DateTime<Local>>>,
+    /// `Some(None)` removes the recurrence
+    pub recurrence: Option<Option<Recurrence>>,
+    pub added_tags: Vec<String>,
+    pub removed_tags: Vec<String>,
+    pub added_items: Vec<String>,
+    /// 1-based indices into the checklist
+    pub checked_items: Vec<usize>,
+    pub unchecked_items: Vec<usize>,
+    /// complete the task if all checklist items are done afterwards
+    pub close: bool,
+    /// replaces the blockers of the task
+    pub blocked_by: Option<Vec<TID>>,
 }
 
 pub struct DeleteGroupCommand {
     pub group: String,
 }
 
+pub struct RenameGroupCommand {
+    pub old: String,
+    /// if this group exists already, the two groups are merged
+    pub new: String,
+}
+
+pub struct OrderGroupsCommand {
+    pub groups: Vec<String>,
+}
+
 pub struct MoveCommand {
     pub group: Option<String>,
     pub prio: Option<Prio>,
-    pub tids: Vec<TID>,
+    pub selection: Selection,
+}
+
+pub struct NotesCommand {
+    pub tid: TID,
+}
+
+pub struct ShowCommand {
+    pub tid: TID,
+}
+
+#[derive(Clone, Copy)]
+pub enum SearchMode {
+    /// case-insensitive substring
+    Substring,
+    /// case-insensitive regular expression
+    Regex,
+    /// fuzzy matching, ranked by how well the task matches
+    Fuzzy,
+}
+
+pub struct FindCommand {
+    pub query: String,
+    pub mode: SearchMode,
+}
+
+pub struct ArchiveCommand {
+    pub query: Option<String>,
+}
+
+pub struct RestoreCommand {
+    pub indices: Vec<usize>,
+}
+
+pub struct PurgeCommand {
+    pub days: u32,
+}
+
+/// A file format that tasks can be exported to.
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    TodoTxt,
+    Markdown,
+    Org,
+    Ics,
+    Csv,
+}
+
+/// A file format that tasks can be imported from.
+#[derive(Clone, Copy)]
+pub enum ImportFormat {
+    TodoTxt,
+    Ics,
+    Csv,
+}
+
+pub struct ExportCommand {
+    pub format: ExportFormat,
+    pub group: Option<String>,
+    pub filter: TaskFilter,
+    /// also export archived tasks, as checked
+    pub include_done: bool,
+}
+
+pub struct ImportCommand {
+    pub format: ImportFormat,
+    pub path: String,
+    /// only show what would change
+    pub dry_run: bool,
 }
 
 pub enum Command {
+    Undo,
+    Redo,
+    CheckData,
     New(NewCommand),
     List(ListCommand),
     Done(DoneCommand),
     Update(UpdateCommand),
     DeleteGroup(DeleteGroupCommand),
+    RenameGroup(RenameGroupCommand),
+    OrderGroups(OrderGroupsCommand),
     Move(MoveCommand),
+    Notes(NotesCommand),
+    Show(ShowCommand),
+    Archive(ArchiveCommand),
+    Restore(RestoreCommand),
+    Purge(PurgeCommand),
+    Config,
+    Find(FindCommand),
+    Tui,
+    Export(ExportCommand),
+    Import(ImportCommand),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Task {
     pub tid: TID,
     pub name: String,
     pub prio: Prio,
     pub date: DateTime<Local>,
     pub group: String,
+    #[serde(default)]
+    pub due: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    /// tasks that need to be done before this one
+    #[serde(default)]
+    pub blocked_by: Vec<TID>,
+    /// fields written by a newer version, kept so that they aren't lost
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Group {
     pub name: String,
     pub tids: Vec<TID>,
+    /// when a task of the group was last added, changed or removed
+    #[serde(default)]
+    pub touched: Option<DateTime<Local>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+/// A completed task. The task keeps its original group and prio.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArchivedTask {
+    pub task: Task,
+    pub completed: DateTime<Local>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Reads the tasks through string keys. A struct with a flattened field is
+/// buffered by serde, which loses the conversion of JSON keys into numbers.
+fn deserialize_tasks<'de, D>(deserializer: D) -> Result<HashMap<TID, Task>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let tasks = HashMap::<String, Task>::deserialize(deserializer)?;
+    tasks
+        .into_iter()
+        .map(|(tid, task)| {
+            let tid = tid.parse::<TID>().map_err(serde::de::Error::custom)?;
+            Ok((tid, task))
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
 pub struct Model {
+    #[serde(deserialize_with = "deserialize_tasks")]
     pub tasks: HashMap<TID, Task>,
     pub groups: HashMap<String, Group>,
+    #[serde(default)]
+    pub archive: Vec<ArchivedTask>,
+    /// the id of the next new task; ids are never handed out twice
+    #[serde(default)]
+    pub next_tid: TID,
+    /// the order of the groups for `GroupSort::Manual`
+    #[serde(default)]
+    pub group_order: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A field of the model that a mutation changed, with its values before and
+/// after it. `None` means that the field didn't exist.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// the keys that lead to the field, e.g. `["tasks", "12", "prio"]`
+    pub path: Vec<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// A single mutation of the model. Only the changed fields are kept, so that
+/// the journal stays small.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub action: String,
+    pub date: DateTime<Local>,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+    /// entries before this index can be undone, the ones after it can be redone
+    pub position: usize,
 }
 
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
-    TIDMissing,
-    TIDsMissing,
     InvalidTID(String),
-    GroupMissing,
     InvalidArgument(String),
     CannotReadDataFile,
     InvalidDataFile,
@@ -88,4 +441,32 @@ pub enum Error {
     NothingToUpdate,
     InvalidGroup(String),
     NothingToMove,
+    CannotLockDataFile,
+    DataFileLocked,
+    CannotReadJournalFile,
+    InvalidJournalFile,
+    NothingToUndo,
+    NothingToRedo,
+    InvalidArchiveIndex(String),
+    InvalidDays(String),
+    InvalidDueDate(String),
+    InvalidRecurrence(String),
+    InvalidTag(String),
+    CannotWriteNotesFile,
+    EditorFailed(String),
+    InvalidChecklistItem(String),
+    DependencyCycle(Vec<TID>),
+    Usage(String),
+    InvalidSelector(String),
+    NothingSelected,
+    NotConfirmed,
+    InvalidData(Vec<String>),
+    CannotReadConfigFile,
+    InvalidConfigFile(String),
+    UnknownProfile(String),
+    InvalidDataFilePath(String),
+    InvalidRegex(String),
+    CannotOpenTerminal,
+    CannotReadImportFile(String),
+    InvalidImportFile(String),
 }
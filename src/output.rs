@@ -0,0 +1,281 @@
+use crate::config::*;
+use crate::display::*;
+use crate::model::*;
+use crate::search::SearchMatch;
+
+use serde_json::{json, Map, Value};
+use std::io::IsTerminal;
+
+/// Collects what a command wants to show. By default everything is printed
+/// right away; with `--json`, a single JSON object is printed at the end.
+pub struct Output {
+    json: bool,
+    flat: bool,
+    yes: bool,
+    messages: Vec<String>,
+    report: Map<String, Value>,
+}
+
+fn task_json(task: &Task, model: &Model) -> Value {
+    let mut value = serde_json::to_value(task).unwrap();
+    let blocked = task
+        .blocked_by
+        .iter()
+        .any(|blocker| model.tasks.contains_key(blocker));
+    value["blocked"] = Value::Bool(blocked);
+    value
+}
+
+fn group_json(group: &Group, model: &Model, filter: &TaskFilter) -> Value {
+    let tasks = group
+        .tids
+        .iter()
+        .map(|tid| model.tasks.get(tid).unwrap())
+        .filter(|task| filter.matches(task))
+        .map(|task| task_json(task, model))
+        .collect::<Vec<Value>>();
+    json!({ "name": group.name, "tasks": tasks })
+}
+
+impl Output {
+    pub fn new(options: &GlobalOptions) -> Self {
+        if options.json {
+            // the JSON must not contain any escape codes
+            colored::control::set_override(false);
+        }
+        Output {
+            json: options.json,
+            flat: options.flat,
+            yes: options.yes,
+            messages: Vec::new(),
+            report: Map::new(),
+        }
+    }
+
+    pub fn message(&mut self, text: &str) {
+        if self.json {
+            self.messages.push(text.trim().to_string());
+        } else {
+            println!("{text}");
+        }
+    }
+
+    /// Records which tasks were created, updated or deleted by a mutation.
+    pub fn changes(&mut self, before: &Model, after: &Model) {
+        if !self.json {
+            return;
+        }
+        let mut created: Vec<TID> = Vec::new();
+        let mut updated: Vec<TID> = Vec::new();
+        for (tid, task) in after.tasks.iter() {
+            match before.tasks.get(tid) {
+                None => created.push(*tid),
+                Some(old) => {
+                    if serde_json::to_value(old).ok() != serde_json::to_value(task).ok() {
+                        updated.push(*tid);
+                    }
+                }
+            }
+        }
+        let mut deleted = before
+            .tasks
+            .keys()
+            .filter(|tid| !after.tasks.contains_key(tid))
+            .copied()
+            .collect::<Vec<TID>>();
+        created.sort_unstable();
+        updated.sort_unstable();
+        deleted.sort_unstable();
+        self.report.insert("created".to_string(), json!(created));
+        self.report.insert("updated".to_string(), json!(updated));
+        self.report.insert("deleted".to_string(), json!(deleted));
+    }
+
+    pub fn model(&mut self, model: &Model, filter: &TaskFilter) {
+        if !self.json {
+            println!("{}", PrintModel::new(model, filter));
+            return;
+        }
+        if self.flat {
+            let mut tasks = model
+                .tasks
+                .values()
+                .filter(|task| filter.matches(task))
+                .collect::<Vec<&Task>>();
+            tasks.sort_by_key(|task| task.tid);
+            let tasks = tasks
+                .into_iter()
+                .map(|task| task_json(task, model))
+                .collect::<Vec<Value>>();
+            self.report.insert("tasks".to_string(), json!(tasks));
+        } else {
+            let groups = ordered_groups(model)
+                .into_iter()
+                .map(|group| group_json(group, model, filter))
+                .filter(|group| {
+                    !config().hide_empty_groups || !group["tasks"].as_array().unwrap().is_empty()
+                })
+                .collect::<Vec<Value>>();
+            self.report.insert("groups".to_string(), json!(groups));
+        }
+    }
+
+    pub fn group(&mut self, group: &Group, model: &Model, filter: &TaskFilter) {
+        if !self.json {
+            println!("{}", PrintGroup::new(group, model, filter));
+            return;
+        }
+        let group = group_json(group, model, filter);
+        if self.flat {
+            self.report.insert("tasks".to_string(), group["tasks"].clone());
+        } else {
+            self.report.insert("groups".to_string(), json!([group]));
+        }
+    }
+
+    /// A list of tasks with a heading, e.g. the tasks that were unblocked.
+    pub fn tasks(&mut self, key: &str, heading: &str, tasks: &[&Task], model: &Model) {
+        if !self.json {
+            println!("\n{heading}:\n");
+            for task in tasks.iter() {
+                println!("  {}", PrintTask::in_model(task, model));
+            }
+            return;
+        }
+        let tasks = tasks
+            .iter()
+            .map(|task| task_json(task, model))
+            .collect::<Vec<Value>>();
+        self.report.insert(key.to_string(), json!(tasks));
+    }
+
+    pub fn task_details(&mut self, task: &Task, model: &Model) {
+        if !self.json {
+            println!("{}", PrintTaskDetails::new(task));
+            return;
+        }
+        self.report
+            .insert("task".to_string(), task_json(task, model));
+    }
+
+    pub fn search(&mut self, query: &str, matches: &[SearchMatch], model: &Model) {
+        if !self.json {
+            println!("{}", PrintSearch::new(query, matches, model));
+            return;
+        }
+        let results = matches
+            .iter()
+            .map(|m| {
+                let spans = |spans: &[std::ops::Range<usize>]| {
+                    spans
+                        .iter()
+                        .map(|span| json!([span.start, span.end]))
+                        .collect::<Vec<Value>>()
+                };
+                json!({
+                    "task": task_json(m.task, model),
+                    "score": m.score,
+                    "name_matches": spans(&m.name_spans),
+                    "notes_line": m.notes_line.as_ref().map(|(line, _)| line),
+                    "notes_matches": m.notes_line.as_ref().map(|(_, s)| spans(s)),
+                })
+            })
+            .collect::<Vec<Value>>();
+        self.report.insert("results".to_string(), json!(results));
+    }
+
+    pub fn archive(&mut self, model: &Model, query: Option<&str>) {
+        if !self.json {
+            println!("{}", PrintArchive::new(model, query));
+            return;
+        }
+        let entries = archive_entries(model, query)
+            .into_iter()
+            .map(|(index, entry)| {
+                json!({
+                    "index": index,
+                    "completed": entry.completed,
+                    "task": entry.task,
+                })
+            })
+            .collect::<Vec<Value>>();
+        self.report.insert("archive".to_string(), json!(entries));
+    }
+
+    /// Applies the `color` setting; JSON never contains colors.
+    pub fn set_color_mode(&self, mode: ColorMode) {
+        if self.json {
+            return;
+        }
+        match mode {
+            ColorMode::Auto => {}
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+
+    pub fn config(&mut self, config: &Config) {
+        if !self.json {
+            println!("# {}\n", get_config_path().display());
+            print!("{}", toml::to_string(config).unwrap());
+            return;
+        }
+        self.report
+            .insert("config".to_string(), serde_json::to_value(config).unwrap());
+    }
+
+    /// Prints an exported file as it is.
+    pub fn export(&mut self, content: &str) {
+        if !self.json {
+            print!("{content}");
+            return;
+        }
+        self.report
+            .insert("export".to_string(), Value::String(content.to_string()));
+    }
+
+    /// Lists the tasks on stderr and asks whether a command should change all
+    /// of them. Only asks if stdin is a terminal and `--yes` wasn't given.
+    pub fn confirm(&self, tasks: &[&Task], model: &Model) -> bool {
+        if self.yes {
+            return true;
+        }
+        if !std::io::stdin().is_terminal() {
+            return false;
+        }
+        eprintln!();
+        for task in tasks.iter() {
+            eprintln!("  {}", PrintTask::in_model(task, model));
+        }
+        eprint!("\nApply to these {} tasks? [y/N] ", tasks.len());
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    }
+
+    /// Prints the collected JSON object; does nothing for the normal output.
+    pub fn finish(mut self) {
+        if !self.json {
+            return;
+        }
+        self.report
+            .insert("messages".to_string(), json!(self.messages));
+        println!("{}", Value::Object(self.report));
+    }
+
+    pub fn error(&self, error: &Error) {
+        if self.json {
+            let value = json!({
+                "error": {
+                    "code": error.code(),
+                    "message": error.to_string(),
+                }
+            });
+            println!("{value}");
+        } else {
+            eprintln!("{error}");
+        }
+    }
+}
@@ -1,292 +1,894 @@
+use crate::config::config;
+use crate::dates::*;
 use crate::model::*;
 
-enum CommandType {
-    New,
-    List,
-    Done,
-    Update,
-    DeleteGroup,
-    Move,
+use std::collections::BTreeMap;
+
+use clap::error::ErrorKind;
+use clap::{Args, Parser, Subcommand};
+
+const LEGACY_HELP: &str = "\
+The original flags still work:
+  todo <words>...     same as `todo add <words>...`, unless the first word is a
+                      command like `find` or `done`
+  todo -d 0,1         same as `todo done 0,1`
+  todo -u 1 ...       same as `todo edit 1 ...`
+  todo -m 0,1 ...     same as `todo mv 0,1 ...`
+  todo -dg <group>    same as `todo rmgroup <group>`
+  todo -rg <old> <new>  same as `todo renamegroup <old> <new>`
+  todo -n 1, -s 1     same as `todo notes 1`, `todo show 1`
+  todo -f <query>     same as `todo find <query>`
+  todo --archive, --restore, --purge, --undo, --redo, --check-data, --tui
+  todo --export <format>, --import <format> <file>
+Options like `--due` can also be written with a single dash (`-due`).";
+
+#[derive(Parser)]
+#[command(
+    name = "todo",
+    version,
+    about = "A small no-bullshit command-line tool for managing to-dos.",
+    after_help = LEGACY_HELP
+)]
+struct Cli {
+    /// Print a JSON object instead of the colored output
+    #[arg(long, global = true)]
+    json: bool,
+    /// List tasks without grouping them (with --json)
+    #[arg(long, global = true)]
+    flat: bool,
+    /// Don't ask before changing many tasks at once
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+    /// Use this data file instead of the default one
+    #[arg(long, global = true, value_name = "PATH")]
+    file: Option<String>,
+    /// Use the data file of a profile from the config file
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    /// How to order the groups [default: `group_sort` from the config file]
+    #[arg(long, global = true, value_parser = ["alpha", "urgent", "size", "recent", "manual"])]
+    sort: Option<String>,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
 }
 
-fn parse_cmd_new(args: &[String]) -> Result<Command, Error> {
-    let mut group: String = "Default".to_string();
-    let mut prio: Prio = Prio::B;
-    let mut task: Vec<String> = Vec::new();
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-g" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::GroupMissing);
-                }
-                group = args[i + 1].clone();
-                i += 2;
-            }
-            "-a" => {
-                prio = Prio::A;
-                i += 1;
-            }
-            "-b" => {
-                prio = Prio::B;
-                i += 1;
-            }
-            "-c" => {
-                prio = Prio::C;
-                i += 1;
-            }
-            word => {
-                task.push(word.to_string());
-                i += 1;
-            }
+#[derive(Args)]
+struct PrioArgs {
+    /// Prio a (important)
+    #[arg(short = 'a', overrides_with_all = ["b", "c"])]
+    a: bool,
+    /// Prio b (normal)
+    #[arg(short = 'b', overrides_with_all = ["a", "c"])]
+    b: bool,
+    /// Prio c (unimportant)
+    #[arg(short = 'c', overrides_with_all = ["a", "b"])]
+    c: bool,
+}
+
+impl PrioArgs {
+    fn prio(&self) -> Option<Prio> {
+        match (self.a, self.b, self.c) {
+            (true, _, _) => Some(Prio::A),
+            (_, true, _) => Some(Prio::B),
+            (_, _, true) => Some(Prio::C),
+            _ => None,
         }
     }
-    Ok(Command::New(NewCommand {
-        task: task.join(" "),
-        group,
-        prio,
-    }))
 }
 
-fn parse_cmd_list(args: &[String]) -> Result<Command, Error> {
-    let mut group: Option<String> = None;
-    let mut prio: Option<Prio> = None;
-    let mut i = 1;
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Add a new task
+    Add(AddArgs),
+    /// List tasks (the default)
+    #[command(visible_alias = "list")]
+    Ls(LsArgs),
+    /// Mark tasks as done, which moves them to the archive
+    Done(DoneArgs),
+    /// Update a task
+    Edit(EditArgs),
+    /// Move tasks to another group and/or change their prio
+    Mv(MvArgs),
+    /// Delete a group together with all its tasks
+    Rmgroup(RmgroupArgs),
+    /// Rename a group, or merge it into another group that exists already
+    Renamegroup(RenamegroupArgs),
+    /// Set the order of the groups for `--sort manual`
+    Ordergroups(OrdergroupsArgs),
+    /// Edit the notes of a task in $EDITOR
+    Notes(TidArgs),
+    /// Show a task with all its details and notes
+    Show(TidArgs),
+    /// Search the names and notes of the tasks
+    Find(FindArgs),
+    /// List archived tasks, optionally only those matching a query
+    Archive(ArchiveArgs),
+    /// Move archived tasks back into their groups
+    Restore(RestoreArgs),
+    /// Delete archived tasks that were completed more than N days ago
+    Purge(PurgeArgs),
+    /// Revert the last change
+    Undo,
+    /// Reapply the last reverted change
+    Redo,
+    /// Print the effective configuration
+    Config,
+    /// Open the full-screen terminal interface
+    Tui,
+    /// Check the data file for inconsistencies
+    CheckData,
+    /// Print the tasks in another format
+    Export(ExportArgs),
+    /// Add the tasks from a file in another format
+    Import(ImportArgs),
+}
+
+#[derive(Args)]
+struct AddArgs {
+    /// The name of the task; words starting with `+` are tags
+    #[arg(required = true)]
+    words: Vec<String>,
+    /// The group of the task [default: `default_group` from the config file]
+    #[arg(short = 'g')]
+    group: Option<String>,
+    #[command(flatten)]
+    prio: PrioArgs,
+    /// Due date, e.g. 2026-11-03, tomorrow, fri, "in 3d", "next month" or eod
+    #[arg(long)]
+    due: Option<String>,
+    /// Recurrence: daily, weekly:mon,thu, monthly:15 or 3d (after completion)
+    #[arg(long)]
+    every: Option<String>,
+    /// Add a checklist item (repeatable)
+    #[arg(long = "item", value_name = "TEXT")]
+    items: Vec<String>,
+    /// Comma-separated ids of tasks that need to be done first
+    #[arg(long, value_name = "TIDS")]
+    after: Option<String>,
+}
+
+#[derive(Args)]
+struct LsArgs {
+    /// Only list tasks with these tags, e.g. `+urgent`
+    tags: Vec<String>,
+    /// Only list tasks without this tag, e.g. `-+waiting`
+    #[arg(short = '+', value_name = "TAG")]
+    excluded_tags: Vec<String>,
+    /// Only list tasks in this group
+    #[arg(short = 'g')]
+    group: Option<String>,
+    #[command(flatten)]
+    prio: PrioArgs,
+}
+
+#[derive(Args)]
+struct DoneArgs {
+    /// Task ids like `1,3-4,9` and/or filters like `g:household`, `p:c`, `+tag` or `@overdue`
+    #[arg(required = true)]
+    selection: Vec<String>,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// Task ids like `1,3-4,9` and/or filters like `g:household`, `p:c`, `+tag` or `@overdue`
+    selection: String,
+    /// The new name of the task; words starting with `+` add tags. Leading
+    /// filters like `g:household`, `p:c` or `@overdue` still belong to the selection
+    words: Vec<String>,
+    /// Remove this tag, e.g. `-+waiting`
+    #[arg(short = '+', value_name = "TAG")]
+    removed_tags: Vec<String>,
+    /// Move the task to this group
+    #[arg(short = 'g')]
+    group: Option<String>,
+    #[command(flatten)]
+    prio: PrioArgs,
+    /// Due date, e.g. 2026-11-03, tomorrow, fri, "in 3d", "next month", eod or none
+    #[arg(long)]
+    due: Option<String>,
+    /// Recurrence: daily, weekly:mon,thu, monthly:15, 3d (after completion) or none
+    #[arg(long)]
+    every: Option<String>,
+    /// Add a checklist item (repeatable)
+    #[arg(long = "item", value_name = "TEXT")]
+    items: Vec<String>,
+    /// Tick checklist items by their comma-separated numbers
+    #[arg(long, value_name = "ITEMS")]
+    check: Vec<String>,
+    /// Untick checklist items by their comma-separated numbers
+    #[arg(long, value_name = "ITEMS")]
+    uncheck: Vec<String>,
+    /// Complete the task if all checklist items are ticked afterwards
+    #[arg(long)]
+    close: bool,
+    /// Comma-separated ids of tasks that need to be done first, or none
+    #[arg(long, value_name = "TIDS")]
+    after: Option<String>,
+}
+
+#[derive(Args)]
+struct MvArgs {
+    /// Task ids like `1,3-4,9` and/or filters like `g:household`, `p:c`, `+tag` or `@overdue`
+    #[arg(required = true)]
+    selection: Vec<String>,
+    /// The new group of the tasks
+    #[arg(short = 'g')]
+    group: Option<String>,
+    #[command(flatten)]
+    prio: PrioArgs,
+}
+
+#[derive(Args)]
+struct RmgroupArgs {
+    /// The name of the group
+    group: String,
+}
+
+#[derive(Args)]
+struct RenamegroupArgs {
+    /// The current name of the group
+    old: String,
+    /// The new name of the group
+    new: String,
+}
+
+#[derive(Args)]
+struct OrdergroupsArgs {
+    /// The groups in the order in which they should be listed
+    #[arg(required = true)]
+    groups: Vec<String>,
+}
+
+#[derive(Args)]
+struct TidArgs {
+    /// The id of the task
+    tid: String,
+}
+
+#[derive(Args)]
+struct FindArgs {
+    /// Case-insensitive text to look for
+    #[arg(required = true)]
+    query: Vec<String>,
+    /// Treat the query as a regular expression
+    #[arg(long, conflicts_with = "fuzzy")]
+    regex: bool,
+    /// Match the names fuzzily and rank the results
+    #[arg(long)]
+    fuzzy: bool,
+}
+
+#[derive(Args)]
+struct ArchiveArgs {
+    /// Only list archived tasks whose name or group contains this
+    query: Vec<String>,
+}
+
+#[derive(Args)]
+struct RestoreArgs {
+    /// Comma-separated archive indices, as shown by `todo archive`
+    indices: String,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[arg(value_parser = ["todotxt", "md", "org", "ics", "csv"])]
+    format: String,
+    /// Also export archived tasks, as checked
+    #[arg(long)]
+    include_done: bool,
+    #[command(flatten)]
+    ls: LsArgs,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    #[arg(value_parser = ["todotxt", "ics", "csv"])]
+    format: String,
+    /// The file to import
+    #[arg(value_name = "FILE")]
+    path: String,
+    /// Only show what would change
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+struct PurgeArgs {
+    /// Purge tasks that were completed more than this many days ago
+    days: String,
+}
+
+/// Subcommand names, including aliases; used to detect the original syntax.
+const SUBCOMMANDS: &[&str] = &[
+    "add", "ls", "list", "done", "edit", "mv", "rmgroup", "renamegroup", "ordergroups", "notes", "show", "find", "archive", "restore",
+    "purge", "undo", "redo", "check-data", "config", "tui", "export", "import", "help",
+];
+
+/// The number of arguments taken by a global flag like `--json` or
+/// `--file <PATH>`, or `None` if `arg` isn't one.
+fn global_arg_len(arg: &str) -> Option<usize> {
+    match arg {
+        "--json" | "--flat" | "--yes" | "-y" => Some(1),
+        "--file" | "--profile" | "--sort" => Some(2),
+        _ if ["--file=", "--profile=", "--sort="]
+            .iter()
+            .any(|prefix| arg.starts_with(prefix)) =>
+        {
+            Some(1)
+        }
+        _ => None,
+    }
+}
+
+/// Translates the original single-dash spelling of options, e.g. `-due`.
+fn translate_option(arg: &str) -> Option<&'static str> {
+    match arg {
+        "-due" => Some("--due"),
+        "-every" => Some("--every"),
+        "-item" => Some("--item"),
+        "-check" => Some("--check"),
+        "-uncheck" => Some("--uncheck"),
+        "-close" => Some("--close"),
+        "-after" => Some("--after"),
+        _ => None,
+    }
+}
+
+/// Translates the original flags that selected the command into a subcommand.
+/// Returns the subcommand and the number of values that follow the flag.
+fn translate_command_flag(arg: &str) -> Option<(&'static str, usize)> {
+    match arg {
+        "-d" => Some(("done", 1)),
+        "-u" => Some(("edit", 1)),
+        "-m" => Some(("mv", 1)),
+        "-dg" => Some(("rmgroup", 1)),
+        "-rg" => Some(("renamegroup", 1)),
+        "-n" => Some(("notes", 1)),
+        "-s" => Some(("show", 1)),
+        "-f" => Some(("find", 1)),
+        "--archive" => Some(("archive", 0)),
+        "--restore" => Some(("restore", 1)),
+        "--purge" => Some(("purge", 1)),
+        "--undo" => Some(("undo", 0)),
+        "--redo" => Some(("redo", 0)),
+        "--check-data" => Some(("check-data", 0)),
+        "--tui" => Some(("tui", 0)),
+        "--export" => Some(("export", 1)),
+        "--import" => Some(("import", 2)),
+        _ => None,
+    }
+}
+
+/// Rewrites the original flag-based syntax (`todo -d 0,1`, `todo buy milk`)
+/// into the subcommand syntax (`todo done 0,1`, `todo add buy milk`).
+fn translate_legacy_args(args: &[String]) -> Vec<String> {
+    let mut translated: Vec<String> = Vec::new();
+    let mut i = 0;
     while i < args.len() {
-        match args[i].as_str() {
-            "-g" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::GroupMissing);
-                }
-                group = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "-a" => {
-                prio = Some(Prio::A);
-                i += 1;
-            }
-            "-b" => {
-                prio = Some(Prio::B);
-                i += 1;
-            }
-            "-c" => {
-                prio = Some(Prio::C);
-                i += 1;
-            }
-            word => {
-                return Err(Error::InvalidArgument(word.to_string()));
+        let arg = translate_option(&args[i]).unwrap_or(&args[i]);
+        // `in 3d` and `next month` span two arguments
+        if arg == "--due" && i + 2 < args.len() && matches!(args[i + 1].as_str(), "in" | "next") {
+            translated.push(arg.to_string());
+            translated.push(format!("{} {}", args[i + 1], args[i + 2]));
+            i += 3;
+        } else {
+            translated.push(arg.to_string());
+            i += 1;
+        }
+    }
+
+    let mut first = 1;
+    while let Some(len) = translated.get(first).and_then(|arg| global_arg_len(arg)) {
+        first += len;
+    }
+    match translated.get(first).map(String::as_str) {
+        Some(arg) if SUBCOMMANDS.contains(&arg) => return translated,
+        Some("-h" | "--help" | "-V" | "--version") => return translated,
+        _ => {}
+    }
+
+    let mut i = 1;
+    while i < translated.len() {
+        let arg = translated[i].as_str();
+        if let Some((subcommand, values)) = translate_command_flag(arg) {
+            // the subcommand and its values have to come before the options
+            let rest = (i + 1 + values).min(translated.len());
+            let mut result = vec![translated[0].clone(), subcommand.to_string()];
+            result.extend_from_slice(&translated[i + 1..rest]);
+            result.extend_from_slice(&translated[1..i]);
+            result.extend_from_slice(&translated[rest..]);
+            return result;
+        }
+        match arg {
+            "-g" | "--due" | "--every" | "--item" | "--after" | "--check" | "--uncheck" => i += 2,
+            "-a" | "-b" | "-c" | "--close" => i += 1,
+            global if global_arg_len(global).is_some() => i += global_arg_len(global).unwrap(),
+            word if word.starts_with('+') || word.starts_with("-+") => i += 1,
+            _ => {
+                translated.insert(1, "add".to_string());
+                return translated;
             }
         }
     }
-    Ok(Command::List(ListCommand { group, prio }))
+    translated.insert(1, "ls".to_string());
+    translated
 }
 
-fn parse_cmd_done(args: &[String]) -> Result<Command, Error> {
-    assert!(args[1] == "-d");
-    if args.len() > 3 {
-        return Err(Error::InvalidArgument(args[3..].join(" ")));
+/// Scans for the flags that apply to every command. This even works if the
+/// rest of the arguments are invalid, so that errors can be reported as JSON.
+pub fn parse_global_options(args: &[String]) -> GlobalOptions {
+    GlobalOptions {
+        json: args.iter().any(|arg| arg == "--json"),
+        flat: args.iter().any(|arg| arg == "--flat"),
+        yes: args.iter().any(|arg| arg == "--yes" || arg == "-y"),
+        file: global_value(args, "--file"),
+        profile: global_value(args, "--profile"),
+        sort: global_value(args, "--sort").and_then(|sort| parse_group_sort(&sort)),
     }
-    if args.len() == 2 {
-        return Err(Error::TIDMissing);
+}
+
+fn parse_group_sort(sort: &str) -> Option<GroupSort> {
+    match sort {
+        "alpha" => Some(GroupSort::Alpha),
+        "urgent" => Some(GroupSort::Urgent),
+        "size" => Some(GroupSort::Size),
+        "recent" => Some(GroupSort::Recent),
+        "manual" => Some(GroupSort::Manual),
+        _ => None,
     }
-    let tid_str = &args[2];
-    let mut tids: Vec<TID> = Vec::new();
+}
 
-    for tid in tid_str.split(",") {
-        let tid: TID = tid
-            .parse()
-            .map_err(|_| Error::InvalidTID(tid.to_string()))?;
-        tids.push(tid);
+/// The value of a global option, given as `--name value` or `--name=value`.
+fn global_value(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix(name) {
+        Some("") => args.get(i + 1).cloned(),
+        Some(rest) => rest.strip_prefix('=').map(|value| value.to_string()),
+        None => None,
+    })
+}
+
+fn parse_export_format(format: &str) -> Result<ExportFormat, Error> {
+    match format {
+        "todotxt" => Ok(ExportFormat::TodoTxt),
+        "md" => Ok(ExportFormat::Markdown),
+        "org" => Ok(ExportFormat::Org),
+        "ics" => Ok(ExportFormat::Ics),
+        "csv" => Ok(ExportFormat::Csv),
+        _ => Err(Error::InvalidArgument(format.to_string())),
     }
-    if tids.is_empty() {
-        return Err(Error::TIDsMissing);
+}
+
+fn parse_import_format(format: &str) -> Result<ImportFormat, Error> {
+    match format {
+        "todotxt" => Ok(ImportFormat::TodoTxt),
+        "ics" => Ok(ImportFormat::Ics),
+        "csv" => Ok(ImportFormat::Csv),
+        _ => Err(Error::InvalidArgument(format.to_string())),
     }
+}
 
-    Ok(Command::Done(DoneCommand { tids }))
+fn parse_tid(tid: &str) -> Result<TID, Error> {
+    tid_from_str(tid).ok_or(Error::InvalidTID(tid.to_string()))
 }
 
-fn parse_cmd_update(args: &[String]) -> Result<Command, Error> {
-    let mut tid: Option<TID> = None;
-    let mut group: Option<String> = None;
-    let mut prio: Option<Prio> = None;
-    let mut task: Vec<String> = Vec::new();
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-u" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::TIDMissing);
-                }
-                let tid_str = &args[i + 1];
-                tid = Some(
-                    tid_str
-                        .parse()
-                        .map_err(|_| Error::InvalidTID(tid_str.to_string()))?,
-                );
-                i += 2;
-            }
-            "-g" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::GroupMissing);
-                }
-                group = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "-a" => {
-                prio = Some(Prio::A);
-                i += 1;
-            }
-            "-b" => {
-                prio = Some(Prio::B);
-                i += 1;
-            }
-            "-c" => {
-                prio = Some(Prio::C);
-                i += 1;
-            }
-            word => {
-                task.push(word.to_string());
-                i += 1;
+fn parse_tids(tids: &str) -> Result<Vec<TID>, Error> {
+    let mut parsed: Vec<TID> = Vec::new();
+    for tid in tids.split(",") {
+        let tid = parse_tid(tid)?;
+        if !parsed.contains(&tid) {
+            parsed.push(tid);
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, Error> {
+    let invalid = || Error::InvalidSelector(selector.to_string());
+    if let Some(group) = selector.strip_prefix("g:") {
+        return match group {
+            "" => Err(invalid()),
+            group => Ok(Selector::Group(group.to_string())),
+        };
+    }
+    if let Some(prio) = selector.strip_prefix("p:") {
+        return match prio {
+            "a" => Ok(Selector::Prio(Prio::A)),
+            "b" => Ok(Selector::Prio(Prio::B)),
+            "c" => Ok(Selector::Prio(Prio::C)),
+            _ => Err(invalid()),
+        };
+    }
+    if selector == "@overdue" {
+        return Ok(Selector::Overdue);
+    }
+    if let Some(tag) = parse_tag(selector) {
+        return Ok(Selector::Tag(tag));
+    }
+    match selector.split_once("-") {
+        Some((from, to)) => {
+            let from = tid_from_str(from).ok_or_else(invalid)?;
+            let to = tid_from_str(to).ok_or_else(invalid)?;
+            if from > to {
+                return Err(invalid());
             }
+            Ok(Selector::Range(from, to))
+        }
+        None => Ok(Selector::TID(tid_from_str(selector).ok_or_else(invalid)?)),
+    }
+}
+
+/// Whether every part of `arg` is a filter like `g:household`, `p:c` or
+/// `@overdue`. Ids and tags don't count, since a new name can start with a
+/// number and `+tag` adds a tag.
+fn is_filter(arg: &str) -> bool {
+    arg.split(",").all(|selector| {
+        matches!(
+            parse_selector(selector),
+            Ok(Selector::Group(_) | Selector::Prio(_) | Selector::Overdue)
+        )
+    })
+}
+
+/// Parses selections like `1,3-4,9` or `g:household p:c`.
+fn parse_selection(args: &[String]) -> Result<Selection, Error> {
+    let mut selectors: Vec<Selector> = Vec::new();
+    for selector in args.iter().flat_map(|arg| arg.split(",")) {
+        selectors.push(parse_selector(selector)?);
+    }
+    Ok(Selection { selectors })
+}
+
+/// Parses the ids of the blockers; `none` yields no blockers.
+fn parse_blockers(blockers: &str) -> Result<Vec<TID>, Error> {
+    if blockers == "none" {
+        return Ok(Vec::new());
+    }
+    parse_tids(blockers)
+}
+
+/// Parses comma-separated 1-based checklist indices.
+fn parse_checklist_indices(lists: &[String]) -> Result<Vec<usize>, Error> {
+    let mut indices: Vec<usize> = Vec::new();
+    for s in lists.iter().flat_map(|list| list.split(",")) {
+        let index = s
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidChecklistItem(s.to_string()))?;
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
+/// Returns the tag for a word like `+urgent`.
+fn parse_tag(word: &str) -> Option<String> {
+    word.strip_prefix('+')
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+}
+
+/// Splits words into the name of a task and its tags.
+fn split_tags(words: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let mut name: Vec<String> = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for word in words {
+        match parse_tag(&word) {
+            Some(tag) if !tags.contains(&tag) => tags.push(tag),
+            Some(_) => {}
+            None => name.push(word),
         }
     }
-    assert!(tid.is_some());
-    if group.is_none() && prio.is_none() && task.is_empty() {
+    (name, tags)
+}
+
+/// Checks the values of `-+`, which are given without the `+`.
+fn check_tags(tags: &[String]) -> Result<(), Error> {
+    match tags.iter().find(|tag| tag.is_empty()) {
+        Some(tag) => Err(Error::InvalidTag(tag.to_string())),
+        None => Ok(()),
+    }
+}
+
+fn convert_add(args: AddArgs) -> Result<Command, Error> {
+    let (task, tags) = split_tags(args.words);
+    let due = match args.due {
+        Some(due) => resolve_due(&due)?,
+        None => None,
+    };
+    let recurrence = match args.every {
+        Some(every) => resolve_recurrence(&every)?,
+        None => None,
+    };
+    let blocked_by = match args.after {
+        Some(after) => parse_blockers(&after)?,
+        None => Vec::new(),
+    };
+    Ok(Command::New(NewCommand {
+        task: task.join(" "),
+        group: args
+            .group
+            .unwrap_or_else(|| config().default_group.clone()),
+        prio: args.prio.prio().unwrap_or(config().default_prio),
+        due,
+        recurrence,
+        tags,
+        items: args.items,
+        blocked_by,
+    }))
+}
+
+fn convert_filter(args: LsArgs) -> Result<(Option<String>, TaskFilter), Error> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in args.tags {
+        tags.push(parse_tag(&word).ok_or(Error::InvalidArgument(word))?);
+    }
+    check_tags(&args.excluded_tags)?;
+    let filter = TaskFilter {
+        prio: args.prio.prio(),
+        tags,
+        excluded_tags: args.excluded_tags,
+    };
+    Ok((args.group, filter))
+}
+
+fn convert_ls(args: LsArgs) -> Result<Command, Error> {
+    let (group, filter) = convert_filter(args)?;
+    Ok(Command::List(ListCommand { group, filter }))
+}
+
+fn convert_export(args: ExportArgs) -> Result<Command, Error> {
+    let (group, filter) = convert_filter(args.ls)?;
+    Ok(Command::Export(ExportCommand {
+        format: parse_export_format(&args.format)?,
+        group,
+        filter,
+        include_done: args.include_done,
+    }))
+}
+
+fn convert_edit(mut args: EditArgs) -> Result<Command, Error> {
+    // `todo edit g:work p:c -a` selects by two filters instead of renaming
+    let count = args.words.iter().take_while(|word| is_filter(word)).count();
+    let mut selection = vec![args.selection];
+    selection.extend(args.words.drain(..count));
+    let selection = parse_selection(&selection)?;
+    let (task, added_tags) = split_tags(args.words);
+    check_tags(&args.removed_tags)?;
+    let due = args.due.map(|due| resolve_due(&due)).transpose()?;
+    let recurrence = args
+        .every
+        .map(|every| resolve_recurrence(&every))
+        .transpose()?;
+    let blocked_by = args.after.map(|after| parse_blockers(&after)).transpose()?;
+    let checked_items = parse_checklist_indices(&args.check)?;
+    let unchecked_items = parse_checklist_indices(&args.uncheck)?;
+    let prio = args.prio.prio();
+
+    if args.group.is_none()
+        && prio.is_none()
+        && due.is_none()
+        && recurrence.is_none()
+        && added_tags.is_empty()
+        && args.removed_tags.is_empty()
+        && args.items.is_empty()
+        && checked_items.is_empty()
+        && unchecked_items.is_empty()
+        && blocked_by.is_none()
+        && task.is_empty()
+    {
         return Err(Error::NothingToUpdate);
     }
     Ok(Command::Update(UpdateCommand {
-        tid: tid.unwrap(),
-        group,
+        selection,
+        group: args.group,
         prio,
         task: if task.is_empty() {
             None
         } else {
             Some(task.join(" "))
         },
+        due,
+        recurrence,
+        added_tags,
+        removed_tags: args.removed_tags,
+        added_items: args.items,
+        checked_items,
+        unchecked_items,
+        close: args.close,
+        blocked_by,
     }))
 }
 
-fn parse_cmd_deletegroup(args: &[String]) -> Result<Command, Error> {
-    let mut group: Option<String> = None;
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-dg" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::GroupMissing);
-                }
-                group = Some(args[i + 1].to_string());
-                i += 2;
-            }
-            word => Err(Error::InvalidArgument(word.to_string()))?,
-        }
+fn convert_mv(args: MvArgs) -> Result<Command, Error> {
+    let prio = args.prio.prio();
+    if args.group.is_none() && prio.is_none() {
+        return Err(Error::NothingToMove);
     }
-
-    assert!(group.is_some());
-    Ok(Command::DeleteGroup(DeleteGroupCommand {
-        group: group.unwrap(),
+    Ok(Command::Move(MoveCommand {
+        group: args.group,
+        prio,
+        selection: parse_selection(&args.selection)?,
     }))
 }
 
-fn parse_cmd_move(args: &[String]) -> Result<Command, Error> {
-    let mut group: Option<String> = None;
-    let mut prio: Option<Prio> = None;
-    let mut tasks: Option<String> = None;
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-m" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::TIDsMissing);
-                }
-                tasks = Some(args[i + 1].to_string());
-                i += 2;
-            }
-            "-g" => {
-                if i + 1 >= args.len() {
-                    return Err(Error::GroupMissing);
-                }
-                group = Some(args[i + 1].to_string());
-                i += 2;
-            }
-            "-a" => {
-                prio = Some(Prio::A);
-                i += 1;
-            }
-            "-b" => {
-                prio = Some(Prio::B);
-                i += 1;
-            }
-            "-c" => {
-                prio = Some(Prio::C);
-                i += 1;
-            }
-            word => {
-                return Err(Error::InvalidArgument(word.to_string()));
-            }
-        }
+fn convert_restore(args: RestoreArgs) -> Result<Command, Error> {
+    let mut indices: Vec<usize> = Vec::new();
+    for s in args.indices.split(",") {
+        let index = s
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidArchiveIndex(s.to_string()))?;
+        indices.push(index);
     }
+    Ok(Command::Restore(RestoreCommand { indices }))
+}
 
-    if group.is_none() && prio.is_none() {
-        return Err(Error::NothingToMove);
+fn convert(command: CliCommand) -> Result<Command, Error> {
+    match command {
+        CliCommand::Add(args) => convert_add(args),
+        CliCommand::Ls(args) => convert_ls(args),
+        CliCommand::Done(args) => Ok(Command::Done(DoneCommand {
+            selection: parse_selection(&args.selection)?,
+        })),
+        CliCommand::Edit(args) => convert_edit(args),
+        CliCommand::Mv(args) => convert_mv(args),
+        CliCommand::Rmgroup(args) => Ok(Command::DeleteGroup(DeleteGroupCommand {
+            group: args.group,
+        })),
+        CliCommand::Renamegroup(args) => Ok(Command::RenameGroup(RenameGroupCommand {
+            old: args.old,
+            new: args.new,
+        })),
+        CliCommand::Ordergroups(args) => Ok(Command::OrderGroups(OrderGroupsCommand {
+            groups: args.groups,
+        })),
+        CliCommand::Notes(args) => Ok(Command::Notes(NotesCommand {
+            tid: parse_tid(&args.tid)?,
+        })),
+        CliCommand::Show(args) => Ok(Command::Show(ShowCommand {
+            tid: parse_tid(&args.tid)?,
+        })),
+        CliCommand::Find(args) => Ok(Command::Find(FindCommand {
+            query: args.query.join(" "),
+            mode: match (args.regex, args.fuzzy) {
+                (true, _) => SearchMode::Regex,
+                (_, true) => SearchMode::Fuzzy,
+                _ => SearchMode::Substring,
+            },
+        })),
+        CliCommand::Archive(args) => Ok(Command::Archive(ArchiveCommand {
+            query: if args.query.is_empty() {
+                None
+            } else {
+                Some(args.query.join(" "))
+            },
+        })),
+        CliCommand::Restore(args) => convert_restore(args),
+        CliCommand::Purge(args) => Ok(Command::Purge(PurgeCommand {
+            days: args
+                .days
+                .parse()
+                .map_err(|_| Error::InvalidDays(args.days.to_string()))?,
+        })),
+        CliCommand::Undo => Ok(Command::Undo),
+        CliCommand::Redo => Ok(Command::Redo),
+        CliCommand::CheckData => Ok(Command::CheckData),
+        CliCommand::Config => Ok(Command::Config),
+        CliCommand::Tui => Ok(Command::Tui),
+        CliCommand::Export(args) => convert_export(args),
+        CliCommand::Import(args) => Ok(Command::Import(ImportCommand {
+            format: parse_import_format(&args.format)?,
+            path: args.path,
+            dry_run: args.dry_run,
+        })),
     }
+}
 
-    assert!(tasks.is_some());
-    let tasks = tasks.unwrap();
-    let mut tids: Vec<TID> = Vec::new();
-    for s in tasks.split(",") {
-        let tid = s
-            .parse::<TID>()
-            .map_err(|_| Error::InvalidTID(s.to_string()))?;
-        tids.push(tid);
+/// Replaces an alias from the config file with the arguments it stands for.
+/// Aliases can't shadow subcommands.
+pub fn expand_alias(args: &[String], aliases: &BTreeMap<String, String>) -> Vec<String> {
+    let mut first = 1;
+    while let Some(len) = args.get(first).and_then(|arg| global_arg_len(arg)) {
+        first += len;
     }
-    if tids.is_empty() {
-        return Err(Error::NothingToMove);
-    }
-
-    Ok(Command::Move(MoveCommand { group, prio, tids }))
+    let expansion = args
+        .get(first)
+        .filter(|name| !SUBCOMMANDS.contains(&name.as_str()))
+        .and_then(|name| aliases.get(name));
+    let Some(expansion) = expansion else {
+        return args.to_vec();
+    };
+    let mut expanded = args[..first].to_vec();
+    expanded.extend(expansion.split_whitespace().map(|arg| arg.to_string()));
+    expanded.extend_from_slice(&args[first + 1..]);
+    expanded
 }
 
+/// Parses the arguments, after `expand_alias`.
 pub fn parse(args: &[String]) -> Result<Command, Error> {
-    let mut command_type = CommandType::List;
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-d" => {
-                command_type = CommandType::Done;
-                break;
-            }
-            "-u" => {
-                command_type = CommandType::Update;
-                break;
-            }
-            "-dg" => {
-                command_type = CommandType::DeleteGroup;
-                break;
-            }
-            "-m" => {
-                command_type = CommandType::Move;
-                break;
-            }
-            "-g" => {
-                i += 2;
-            }
-            "-a" | "-b" | "-c" => {
-                i += 1;
-            }
-            _ => {
-                command_type = CommandType::New;
-                break;
+    let args = translate_legacy_args(args);
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+            e.exit()
+        }
+        Err(e) => return Err(Error::Usage(e.render().to_string())),
+    };
+    match cli.command {
+        Some(command) => convert(command),
+        None => Ok(Command::List(ListCommand {
+            group: None,
+            filter: TaskFilter::default(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        std::iter::once("todo")
+            .chain(line.split_whitespace())
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    fn parse_update(line: &str) -> UpdateCommand {
+        match parse(&args(line)) {
+            Ok(Command::Update(cmd)) => cmd,
+            _ => panic!("`{line}` isn't an update"),
+        }
+    }
+
+    #[test]
+    fn import_with_a_data_file() {
+        for line in [
+            "--file data.json --import csv tasks.csv",
+            "import csv tasks.csv --file data.json",
+        ] {
+            assert_eq!(parse_global_options(&args(line)).file.as_deref(), Some("data.json"));
+            match parse(&args(line)) {
+                Ok(Command::Import(cmd)) => assert_eq!(cmd.path, "tasks.csv"),
+                _ => panic!("`{line}` isn't an import"),
             }
         }
     }
 
-    match command_type {
-        CommandType::New => parse_cmd_new(args),
-        CommandType::List => parse_cmd_list(args),
-        CommandType::Done => parse_cmd_done(args),
-        CommandType::Update => parse_cmd_update(args),
-        CommandType::DeleteGroup => parse_cmd_deletegroup(args),
-        CommandType::Move => parse_cmd_move(args),
+    #[test]
+    fn update_takes_leading_filters_as_selection() {
+        let cmd = parse_update("-u g:work p:c -a");
+        assert!(matches!(
+            cmd.selection.selectors.as_slice(),
+            [Selector::Group(group), Selector::Prio(Prio::C)] if group == "work"
+        ));
+        assert!(cmd.task.is_none());
+        assert_eq!(cmd.prio, Some(Prio::A));
+    }
+
+    #[test]
+    fn update_takes_ids_only_from_the_first_argument() {
+        let cmd = parse_update("edit 4,6-7 @overdue --due none");
+        assert!(matches!(
+            cmd.selection.selectors.as_slice(),
+            [Selector::TID(4), Selector::Range(6, 7), Selector::Overdue]
+        ));
+        assert!(cmd.task.is_none());
+    }
+
+    #[test]
+    fn update_renames_to_a_name_starting_with_a_number() {
+        let cmd = parse_update("-u 0 2 apples");
+        assert!(matches!(cmd.selection.selectors.as_slice(), [Selector::TID(0)]));
+        assert_eq!(cmd.task.as_deref(), Some("2 apples"));
+    }
+
+    #[test]
+    fn update_renames_after_the_selection() {
+        let cmd = parse_update("-u 4 g:work new name +waiting");
+        assert!(matches!(
+            cmd.selection.selectors.as_slice(),
+            [Selector::TID(4), Selector::Group(group)] if group == "work"
+        ));
+        assert_eq!(cmd.task.as_deref(), Some("new name"));
+        assert_eq!(cmd.added_tags, vec!["waiting".to_string()]);
     }
 }
@@ -1,69 +1,215 @@
-use crate::display::*;
+use crate::config::config;
+use crate::dates::*;
 use crate::model::*;
+use crate::output::*;
+use crate::search::search;
 use crate::storage::*;
+use crate::export::*;
+use crate::journal::*;
+use crate::ics;
+use crate::spreadsheet;
+use crate::todotxt;
+use crate::tui::run_tui;
 
-fn find_new_tid(model: &Model) -> TID {
-    for tid in 0..TID::MAX {
-        if !model.tasks.contains_key(&tid) {
-            return tid;
+use colored::Colorize;
+use serde_json::Map;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+/// How many mutations are kept in the journal for undo.
+const JOURNAL_LENGTH: usize = 100;
+
+/// Selections by range or filter that match more tasks than this need to be
+/// confirmed.
+const CONFIRM_THRESHOLD: usize = 3;
+
+/// Hands out the id for a new task. Ids are never reused, so that a task id
+/// in a script or in the shell history can't refer to a different task later.
+fn new_tid(model: &mut Model) -> TID {
+    let tid = model.next_tid;
+    model.next_tid += 1;
+    tid
+}
+
+fn format_tids(tids: &[TID]) -> String {
+    tids.iter()
+        .map(|tid| tid_to_string(*tid))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Marks the groups whose tasks were added, changed or removed as touched now.
+fn touch_groups(before: &Model, model: &mut Model) {
+    let now = chrono::Local::now();
+    for group in model.groups.values_mut() {
+        let old = before.groups.get(&group.name);
+        let changed = old.is_none_or(|old| old.tids != group.tids)
+            || group.tids.iter().any(|tid| {
+                let task = serde_json::to_value(model.tasks.get(tid)).ok();
+                task != serde_json::to_value(before.tasks.get(tid)).ok()
+            });
+        if changed {
+            group.touched = Some(now);
+        }
+    }
+}
+
+/// Writes the model and records the mutation in the journal so that it can be undone.
+fn save_model(model: &mut Model, before: Model, action: String, out: &mut Output) -> Result<(), Error> {
+    out.changes(&before, model);
+    record_change(model, before, action)
+}
+
+/// Like `save_model`, for callers that show the changes themselves.
+pub fn record_change(model: &mut Model, before: Model, action: String) -> Result<(), Error> {
+    touch_groups(&before, model);
+    // a broken journal has to fail the command before the data file is written
+    let mut journal = read_journal()?;
+    // a new mutation discards everything that could have been redone
+    journal.entries.truncate(journal.position);
+    journal.entries.push(JournalEntry {
+        action,
+        date: chrono::Local::now(),
+        changes: diff_models(&before, model)?,
+    });
+    if journal.entries.len() > JOURNAL_LENGTH {
+        let excess = journal.entries.len() - JOURNAL_LENGTH;
+        journal.entries.drain(..excess);
+    }
+    journal.position = journal.entries.len();
+    write_model(model)?;
+    write_journal(&journal)
+}
+
+/// Returns the ids of the selected tasks in ascending order. Fails if a task
+/// that was listed by its id doesn't exist or if nothing matches.
+fn select_tasks(selection: &Selection, model: &Model) -> Result<Vec<TID>, Error> {
+    for selector in selection.selectors.iter() {
+        if let Selector::TID(tid) = selector {
+            if !model.tasks.contains_key(tid) {
+                return Err(Error::InvalidTID(tid_to_string(*tid)));
+            }
         }
     }
-    panic!("all task ids are occupied");
+    let mut tids = model
+        .tasks
+        .values()
+        .filter(|task| selection.matches(task))
+        .map(|task| task.tid)
+        .collect::<Vec<TID>>();
+    if tids.is_empty() {
+        return Err(Error::NothingSelected);
+    }
+    tids.sort_unstable();
+    Ok(tids)
+}
+
+/// Asks for confirmation before a range or filter changes many tasks at once.
+fn confirm_selection(
+    selection: &Selection,
+    tids: &[TID],
+    model: &Model,
+    out: &Output,
+) -> Result<(), Error> {
+    if selection.is_explicit() || tids.len() <= CONFIRM_THRESHOLD {
+        return Ok(());
+    }
+    let tasks = tids
+        .iter()
+        .map(|tid| model.tasks.get(tid).unwrap())
+        .collect::<Vec<&Task>>();
+    if out.confirm(&tasks, model) {
+        Ok(())
+    } else {
+        Err(Error::NotConfirmed)
+    }
+}
+
+/// Orders tasks by prio, then by due date (tasks without one last) and then by
+/// creation date.
+pub fn sort_tids(tids: &mut [TID], tasks: &HashMap<TID, Task>) {
+    tids.sort_by_key(|tid| {
+        let task = tasks.get(tid).unwrap();
+        (task.prio, task.due.is_none(), task.due, task.date)
+    });
+}
+
+/// Inserts the task into the model and into its group, which is created if
+/// it doesn't exist yet.
+fn insert_task(task: Task, model: &mut Model) {
+    let tid = task.tid;
+    let group_name = task.group.clone();
+    model.tasks.insert(tid, task);
+    if !model.groups.contains_key(&group_name) {
+        let group = Group {
+            name: group_name.clone(),
+            tids: Vec::new(),
+            touched: None,
+            extra: Map::new(),
+        };
+        model.groups.insert(group_name.clone(), group);
+    }
+
+    let group = model.groups.get_mut(&group_name).unwrap();
+    group.tids.push(tid);
+    sort_tids(&mut group.tids, &model.tasks);
 }
 
-fn process_cmd_new(cmd: NewCommand) -> Result<(), Error> {
+fn process_cmd_new(cmd: NewCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
     let mut model = read_model()?;
-    let tid = find_new_tid(&model);
-    model.tasks.insert(
-        tid,
+    let before = model.clone();
+    let tid = new_tid(&mut model);
+    check_blockers(tid, &cmd.blocked_by, &model)?;
+    insert_task(
         Task {
             tid,
             name: cmd.task,
             date: chrono::Local::now(),
             prio: cmd.prio,
             group: cmd.group.clone(),
+            due: cmd.due,
+            recurrence: cmd.recurrence,
+            tags: cmd.tags,
+            notes: None,
+            checklist: cmd
+                .items
+                .into_iter()
+                .map(|text| ChecklistItem {
+                    text,
+                    done: false,
+                    extra: Map::new(),
+                })
+                .collect(),
+            blocked_by: cmd.blocked_by,
+            extra: Map::new(),
         },
+        &mut model,
     );
-    if !model.groups.contains_key(&cmd.group) {
-        let group = Group {
-            name: cmd.group.clone(),
-            tids: Vec::new(),
-        };
-        model.groups.insert(cmd.group.clone(), group);
-    }
-    {
-        let group = model.groups.get_mut(&cmd.group).unwrap();
-        group.tids.push(tid);
+    save_model(&mut model, before, format!("new task {}", tid_to_string(tid)), out)?;
 
-        group.tids.sort_by_key(|tid| {
-            let task = model.tasks.get(tid).unwrap();
-            (task.prio, task.date)
-        });
-    }
-    write_model(&model)?;
-
-    println!("\nNew task with id {tid}.");
+    out.message(&format!("\nNew task with id {}.", tid_to_string(tid)));
     let group = model.groups.get(&cmd.group).unwrap();
-    println!("{}", PrintGroup::new(group, &model, None));
+    out.group(group, &model, &TaskFilter::default());
 
     Ok(())
 }
 
-fn process_cmd_list(cmd: ListCommand) -> Result<(), Error> {
+fn process_cmd_list(cmd: ListCommand, out: &mut Output) -> Result<(), Error> {
     let model = read_model()?;
     if let Some(group_name) = cmd.group {
         if !model.groups.contains_key(&group_name) {
             return Err(Error::InvalidGroup(group_name));
         }
         let group = model.groups.get(&group_name).unwrap();
-        println!("{}", PrintGroup::new(group, &model, cmd.prio));
+        out.group(group, &model, &cmd.filter);
     } else {
-        println!("{}", PrintModel::new(&model, cmd.prio));
+        out.model(&model, &cmd.filter);
     }
     Ok(())
 }
 
-fn delete_task(tid: TID, model: &mut Model) {
+fn delete_task(tid: TID, model: &mut Model) -> Task {
     let task = model.tasks.get(&tid).unwrap();
     let group: &mut Group = model.groups.get_mut(&task.group).unwrap();
     let index = group.tids.iter().position(|t| *t == tid).unwrap();
@@ -72,33 +218,148 @@ fn delete_task(tid: TID, model: &mut Model) {
     if group.tids.is_empty() {
         model.groups.remove(&task.group);
     }
-    model.tasks.remove(&tid);
+    model.tasks.remove(&tid).unwrap()
 }
 
-fn process_cmd_done(cmd: DoneCommand) -> Result<(), Error> {
-    let mut model = read_model()?;
+/// Removes `tid` from the blockers of all other tasks and returns the tasks
+/// that are not blocked anymore because of that.
+fn remove_blocker(tid: TID, model: &mut Model) -> Vec<TID> {
+    let mut unblocked: Vec<TID> = Vec::new();
+    for task in model.tasks.values_mut() {
+        if let Some(index) = task.blocked_by.iter().position(|t| *t == tid) {
+            task.blocked_by.remove(index);
+            if task.blocked_by.is_empty() {
+                unblocked.push(task.tid);
+            }
+        }
+    }
+    unblocked.sort_unstable();
+    unblocked
+}
 
-    assert!(!cmd.tids.is_empty());
-    // make sure that the tids are valid and return error otherwise
-    for tid in cmd.tids.iter() {
-        let _ = model
-            .tasks
-            .get(tid)
-            .ok_or(Error::InvalidTID(tid.to_string()))?;
+/// Returns the chain of blockers that leads from `from` to `to`, if any.
+fn find_dependency_path(from: TID, to: TID, model: &Model) -> Option<Vec<TID>> {
+    if from == to {
+        return Some(vec![to]);
+    }
+    let task = model.tasks.get(&from)?;
+    task.blocked_by.iter().find_map(|blocker| {
+        let mut path = find_dependency_path(*blocker, to, model)?;
+        path.insert(0, from);
+        Some(path)
+    })
+}
+
+/// Makes sure that the blockers exist and that `tid` being blocked by them
+/// doesn't introduce a cycle.
+fn check_blockers(tid: TID, blockers: &[TID], model: &Model) -> Result<(), Error> {
+    for blocker in blockers.iter() {
+        if !model.tasks.contains_key(blocker) {
+            return Err(Error::InvalidTID(tid_to_string(*blocker)));
+        }
+        if let Some(mut path) = find_dependency_path(*blocker, tid, model) {
+            path.insert(0, tid);
+            return Err(Error::DependencyCycle(path));
+        }
     }
+    Ok(())
+}
+
+pub struct Completion {
+    /// the next occurrence of a recurring task
+    pub next: Option<TID>,
+    /// tasks that are not blocked anymore
+    pub unblocked: Vec<TID>,
+}
+
+/// Moves the task to the archive and unblocks the tasks waiting for it. If the
+/// task recurs, its next occurrence is created in the same group.
+pub fn complete_task(tid: TID, model: &mut Model) -> Completion {
+    let task = delete_task(tid, model);
+    let unblocked = remove_blocker(tid, model);
+    let next = task.recurrence.as_ref().map(|recurrence| Task {
+        tid: 0,
+        name: task.name.clone(),
+        prio: task.prio,
+        date: chrono::Local::now(),
+        group: task.group.clone(),
+        due: next_occurrence(recurrence, task.due),
+        recurrence: Some(recurrence.clone()),
+        tags: task.tags.clone(),
+        notes: task.notes.clone(),
+        // the next occurrence starts with a fresh checklist
+        checklist: task
+            .checklist
+            .iter()
+            .map(|item| ChecklistItem {
+                text: item.text.clone(),
+                done: false,
+                extra: item.extra.clone(),
+            })
+            .collect(),
+        blocked_by: Vec::new(),
+        extra: task.extra.clone(),
+    });
+    model.archive.push(ArchivedTask {
+        task,
+        completed: chrono::Local::now(),
+        extra: Map::new(),
+    });
+
+    let next = next.map(|mut next| {
+        next.tid = new_tid(model);
+        let next_tid = next.tid;
+        insert_task(next, model);
+        next_tid
+    });
+    Completion { next, unblocked }
+}
 
-    for tid in cmd.tids.iter() {
-        delete_task(*tid, &mut model);
+fn print_completions(completions: &[(TID, Completion)], model: &Model, out: &mut Output) {
+    for (tid, completion) in completions.iter() {
+        if let Some(next_tid) = completion.next {
+            let next = model.tasks.get(&next_tid).unwrap();
+            out.message(&format!(
+                "Task {} recurs, the next occurrence is task {} ({}).",
+                tid_to_string(*tid),
+                tid_to_string(next_tid),
+                next.recurrence.as_ref().unwrap()
+            ));
+        }
     }
-    write_model(&model)?;
+    // tasks that were completed in the same go are gone by now
+    let unblocked = completions
+        .iter()
+        .flat_map(|(_, completion)| completion.unblocked.iter())
+        .filter_map(|tid| model.tasks.get(tid))
+        .collect::<Vec<&Task>>();
+    if !unblocked.is_empty() {
+        out.tasks("unblocked", "No longer blocked", &unblocked, model);
+    }
+}
+
+fn process_cmd_done(cmd: DoneCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    let tids = select_tasks(&cmd.selection, &model)?;
+    confirm_selection(&cmd.selection, &tids, &model, out)?;
+
+    let completions = tids
+        .iter()
+        .map(|tid| (*tid, complete_task(*tid, &mut model)))
+        .collect::<Vec<(TID, Completion)>>();
+    save_model(&mut model, before, format!("done {}", format_tids(&tids)), out)?;
 
-    println!("\nDelete successful.");
-    println!("{}", PrintModel::new(&model, None));
+    out.message("\nMoved to the archive.");
+    print_completions(&completions, &model, out);
+    out.model(&model, &TaskFilter::default());
 
     Ok(())
 }
 
-fn move_task(tid: TID, model: &mut Model, new_group_name: &str) {
+pub fn move_task(tid: TID, model: &mut Model, new_group_name: &str) {
     let task = model.tasks.get(&tid).unwrap();
 
     if new_group_name == task.group {
@@ -121,6 +382,8 @@ fn move_task(tid: TID, model: &mut Model, new_group_name: &str) {
         let group = Group {
             name: new_group_name.to_string(),
             tids: Vec::new(),
+            touched: None,
+            extra: Map::new(),
         };
         model.groups.insert(new_group_name.to_string(), group);
     }
@@ -133,117 +396,992 @@ fn move_task(tid: TID, model: &mut Model, new_group_name: &str) {
     task.group = new_group_name.to_string();
 }
 
-fn process_cmd_update(cmd: UpdateCommand) -> Result<(), Error> {
-    let tid = cmd.tid;
-    let mut model = read_model()?;
-
-    // make sure that the task exists
-    let _ = model
-        .tasks
-        .get(&tid)
-        .ok_or(Error::InvalidTID(tid.to_string()))?;
+/// Applies the changes of an update command to a single task.
+fn update_task(tid: TID, cmd: &UpdateCommand, model: &mut Model) -> Result<(), Error> {
+    if let Some(ref blockers) = cmd.blocked_by {
+        check_blockers(tid, blockers, model)?;
+    }
 
     {
         let task = model.tasks.get_mut(&tid).unwrap();
         if let Some(prio) = cmd.prio {
             task.prio = prio;
         }
-        if let Some(name) = cmd.task {
-            task.name = name;
+        if let Some(ref name) = cmd.task {
+            task.name = name.clone();
+        }
+        if let Some(due) = cmd.due {
+            task.due = due;
+        }
+        if let Some(ref recurrence) = cmd.recurrence {
+            task.recurrence = recurrence.clone();
+        }
+        task.tags.retain(|tag| !cmd.removed_tags.contains(tag));
+        for tag in cmd.added_tags.iter() {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        if let Some(ref blockers) = cmd.blocked_by {
+            task.blocked_by = blockers.clone();
+        }
+        for text in cmd.added_items.iter() {
+            task.checklist.push(ChecklistItem {
+                text: text.clone(),
+                done: false,
+                extra: Map::new(),
+            });
+        }
+        for (indices, done) in [(&cmd.checked_items, true), (&cmd.unchecked_items, false)] {
+            for index in indices.iter() {
+                // the items are numbered starting at 1
+                let item = index
+                    .checked_sub(1)
+                    .and_then(|i| task.checklist.get_mut(i))
+                    .ok_or(Error::InvalidChecklistItem(index.to_string()))?;
+                item.done = done;
+            }
         }
     }
     if let Some(ref group_name) = cmd.group {
-        move_task(tid, &mut model, group_name);
+        move_task(tid, model, group_name);
     }
 
     // figure out the group of the task in order to sort all the tasks in the group
-    {
-        let task = model.tasks.get(&tid).unwrap();
-        let group = model.groups.get_mut(&task.group).unwrap();
-        group.tids.sort_by_key(|tid| {
-            let task = model.tasks.get(tid).unwrap();
-            (task.prio, task.date)
-        });
+    let task = model.tasks.get(&tid).unwrap();
+    let group = model.groups.get_mut(&task.group).unwrap();
+    sort_tids(&mut group.tids, &model.tasks);
+
+    Ok(())
+}
+
+fn process_cmd_update(cmd: UpdateCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    let tids = select_tasks(&cmd.selection, &model)?;
+    confirm_selection(&cmd.selection, &tids, &model, out)?;
+    for tid in tids.iter() {
+        update_task(*tid, &cmd, &mut model)?;
     }
 
-    write_model(&model)?;
+    let closed = tids
+        .iter()
+        .copied()
+        .filter(|tid| {
+            let task = model.tasks.get(tid).unwrap();
+            cmd.close
+                && !task.checklist.is_empty()
+                && task.checklist.iter().all(|item| item.done)
+        })
+        .collect::<Vec<TID>>();
+    // with a single task, only its group is shown afterwards
+    let group_name = match tids.as_slice() {
+        [tid] => Some(model.tasks.get(tid).unwrap().group.clone()),
+        _ => None,
+    };
+    let completions = closed
+        .iter()
+        .map(|tid| (*tid, complete_task(*tid, &mut model)))
+        .collect::<Vec<(TID, Completion)>>();
+
+    let action = match tids.as_slice() {
+        [tid] => format!("update task {}", tid_to_string(*tid)),
+        _ => format!("update tasks {}", format_tids(&tids)),
+    };
+    save_model(&mut model, before, action, out)?;
 
-    println!("\nUpdate successful.");
-    let task = model.tasks.get(&tid).unwrap();
-    let group = model.groups.get(&task.group).unwrap();
-    println!("{}", PrintGroup::new(group, &model, None));
+    if completions.is_empty() {
+        out.message("\nUpdate successful.");
+    } else {
+        out.message(&format!(
+            "\nAll checklist items are done, moved {} {} to the archive.",
+            if closed.len() == 1 { "task" } else { "tasks" },
+            format_tids(&closed)
+        ));
+        print_completions(&completions, &model, out);
+    }
+    match group_name.and_then(|name| model.groups.get(&name)) {
+        Some(group) => out.group(group, &model, &TaskFilter::default()),
+        None => out.model(&model, &TaskFilter::default()),
+    }
 
     Ok(())
 }
 
-fn process_cmd_move(cmd: MoveCommand) -> Result<(), Error> {
+fn process_cmd_move(cmd: MoveCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
     let mut model = read_model()?;
+    let before = model.clone();
 
-    // check that all tids exist
-    for tid in cmd.tids.iter() {
-        let _ = model
-            .tasks
-            .get(&tid)
-            .ok_or(Error::InvalidTID(tid.to_string()))?;
-    }
+    let tids = select_tasks(&cmd.selection, &model)?;
+    confirm_selection(&cmd.selection, &tids, &model, out)?;
 
     if let Some(prio) = cmd.prio {
-        for tid in cmd.tids.iter() {
+        for tid in tids.iter() {
             let task = model.tasks.get_mut(tid).unwrap();
             task.prio = prio;
         }
     }
 
     if let Some(ref new_group_name) = cmd.group {
-        for tid in cmd.tids.iter() {
+        for tid in tids.iter() {
             move_task(*tid, &mut model, new_group_name);
         }
     }
 
     // sort all the groups
     for group in model.groups.values_mut() {
-        group.tids.sort_by_key(|tid| {
-            let task = model.tasks.get(tid).unwrap();
-            (task.prio, task.date)
-        })
+        sort_tids(&mut group.tids, &model.tasks);
     }
 
-    write_model(&model)?;
+    save_model(&mut model, before, format!("move {}", format_tids(&tids)), out)?;
 
-    println!("\nMove successful.");
-    println!("{}", PrintModel::new(&model, None));
+    out.message("\nMove successful.");
+    out.model(&model, &TaskFilter::default());
 
     Ok(())
 }
 
-fn process_cmd_deletegroup(cmd: DeleteGroupCommand) -> Result<(), Error> {
+fn process_cmd_deletegroup(cmd: DeleteGroupCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
     let mut model = read_model()?;
+    let before = model.clone();
 
     if !model.groups.contains_key(&cmd.group) {
         return Err(Error::InvalidGroup(cmd.group));
     }
 
-    let group = model.groups.get(&cmd.group).unwrap();
+    let group = model.groups.remove(&cmd.group).unwrap();
+    model.group_order.retain(|name| *name != cmd.group);
     for tid in group.tids.iter() {
         model.tasks.remove(tid);
     }
-    model.groups.remove(&cmd.group);
+    // tasks in other groups must not wait for the deleted tasks anymore
+    let mut dangling: Vec<(TID, TID)> = Vec::new();
+    for task in model.tasks.values_mut() {
+        task.blocked_by.retain(|blocker| {
+            let deleted = group.tids.contains(blocker);
+            if deleted {
+                dangling.push((task.tid, *blocker));
+            }
+            !deleted
+        });
+    }
+    dangling.sort_unstable();
+
+    save_model(&mut model, before, format!("delete group {}", cmd.group), out)?;
+
+    out.message("\nDeleted group successfully.");
+    for (tid, blocker) in dangling.iter() {
+        out.message(&format!(
+            "{} task {} was blocked by the deleted task {}.",
+            "Warning:".yellow(),
+            tid_to_string(*tid),
+            tid_to_string(*blocker)
+        ));
+    }
+    out.model(&model, &TaskFilter::default());
+
+    Ok(())
+}
+
+fn process_cmd_renamegroup(cmd: RenameGroupCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    if cmd.new.is_empty() {
+        return Err(Error::InvalidGroup(cmd.new));
+    }
+    if cmd.old == cmd.new {
+        return Err(Error::NothingToUpdate);
+    }
+    let old_group = model
+        .groups
+        .remove(&cmd.old)
+        .ok_or(Error::InvalidGroup(cmd.old.clone()))?;
+    for tid in old_group.tids.iter() {
+        let task = model.tasks.get_mut(tid).unwrap();
+        task.group = cmd.new.clone();
+    }
+
+    // the manual order keeps the position of the group
+    let merged = model.groups.contains_key(&cmd.new);
+    if merged {
+        model.group_order.retain(|name| *name != cmd.old);
+    } else if let Some(name) = model.group_order.iter_mut().find(|name| **name == cmd.old) {
+        *name = cmd.new.clone();
+    }
+    let group = model.groups.entry(cmd.new.clone()).or_insert(Group {
+        name: cmd.new.clone(),
+        tids: Vec::new(),
+        touched: None,
+        extra: old_group.extra,
+    });
+    group.tids.extend(old_group.tids);
+    sort_tids(&mut group.tids, &model.tasks);
+
+    save_model(
+        &mut model,
+        before,
+        format!("rename group {} to {}", cmd.old, cmd.new),
+        out,
+    )?;
+
+    if merged {
+        out.message(&format!("\nMerged group `{}` into `{}`.", cmd.old, cmd.new));
+    } else {
+        out.message(&format!("\nRenamed group `{}` to `{}`.", cmd.old, cmd.new));
+    }
+    let group = model.groups.get(&cmd.new).unwrap();
+    out.group(group, &model, &TaskFilter::default());
+
+    Ok(())
+}
+
+fn process_cmd_ordergroups(cmd: OrderGroupsCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    for name in cmd.groups.iter() {
+        if !model.groups.contains_key(name) {
+            return Err(Error::InvalidGroup(name.to_string()));
+        }
+    }
+    let mut order: Vec<String> = Vec::new();
+    for name in cmd.groups {
+        if !order.contains(&name) {
+            order.push(name);
+        }
+    }
+    model.group_order = order;
+    save_model(&mut model, before, "order groups".to_string(), out)?;
+
+    if config().group_sort == GroupSort::Manual {
+        out.message("\nChanged the order of the groups.");
+    } else {
+        out.message(
+            "\nChanged the order of the groups. It is used with `--sort manual` or `group_sort = \"manual\"` in the config file.",
+        );
+    }
+    out.model(&model, &TaskFilter::default());
+
+    Ok(())
+}
+
+/// A change from the journal that was undone or redone.
+pub struct Replay {
+    pub action: String,
+    /// the model before it was undone or redone
+    pub before: Model,
+    pub after: Model,
+}
+
+/// Reverts the last change of the journal. The data file needs to be locked.
+pub fn undo_change() -> Result<Replay, Error> {
+    let mut journal = read_journal()?;
+    if journal.position == 0 {
+        return Err(Error::NothingToUndo);
+    }
+    let before = read_model()?;
+    journal.position -= 1;
+    let entry = &journal.entries[journal.position];
+    let mut after = revert_changes(&before, &entry.changes)?;
+    // the ids of tasks created by the undone change are not handed out again
+    after.next_tid = after.next_tid.max(before.next_tid);
+    let action = entry.action.clone();
+    write_model(&after)?;
+    write_journal(&journal)?;
+    Ok(Replay { action, before, after })
+}
+
+/// Reapplies the last undone change. The data file needs to be locked.
+pub fn redo_change() -> Result<Replay, Error> {
+    let mut journal = read_journal()?;
+    if journal.position == journal.entries.len() {
+        return Err(Error::NothingToRedo);
+    }
+    let before = read_model()?;
+    let entry = &journal.entries[journal.position];
+    let after = replay_changes(&before, &entry.changes)?;
+    let action = entry.action.clone();
+    journal.position += 1;
+    write_model(&after)?;
+    write_journal(&journal)?;
+    Ok(Replay { action, before, after })
+}
+
+fn process_cmd_undo(out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let undone = undo_change()?;
+
+    out.changes(&undone.before, &undone.after);
+    out.message(&format!("\nUndid `{}`.", undone.action));
+    out.model(&undone.after, &TaskFilter::default());
+
+    Ok(())
+}
+
+fn process_cmd_redo(out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let redone = redo_change()?;
+
+    out.changes(&redone.before, &redone.after);
+    out.message(&format!("\nRedid `{}`.", redone.action));
+    out.model(&redone.after, &TaskFilter::default());
 
-    write_model(&model)?;
+    Ok(())
+}
+
+/// Whether following the blockers of `tid` leads back to it. `visited` holds
+/// the tasks whose blockers were already followed.
+fn has_cycle(tid: TID, path: &mut Vec<TID>, visited: &mut HashSet<TID>, model: &Model) -> bool {
+    if path.contains(&tid) {
+        return true;
+    }
+    if !visited.insert(tid) {
+        return false;
+    }
+    let Some(task) = model.tasks.get(&tid) else {
+        return false;
+    };
+    path.push(tid);
+    let found = task
+        .blocked_by
+        .iter()
+        .any(|blocker| has_cycle(*blocker, path, visited, model));
+    path.pop();
+    found
+}
+
+/// Checks the invariants of the model and describes every violation.
+fn find_problems(model: &Model) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+    let mut tids = model.tasks.keys().copied().collect::<Vec<TID>>();
+    tids.sort_unstable();
+
+    for tid in tids.iter() {
+        let task = model.tasks.get(tid).unwrap();
+        let id = tid_to_string(*tid);
+        if task.tid != *tid {
+            problems.push(format!(
+                "Task {id} is stored under the id {}.",
+                tid_to_string(task.tid)
+            ));
+        }
+        match model.groups.get(&task.group) {
+            None => problems.push(format!(
+                "Task {id} is in group `{}`, which doesn't exist.",
+                task.group
+            )),
+            Some(group) if !group.tids.contains(tid) => problems.push(format!(
+                "Task {id} is not listed in its group `{}`.",
+                task.group
+            )),
+            Some(_) => {}
+        }
+        for blocker in task.blocked_by.iter() {
+            if !model.tasks.contains_key(blocker) {
+                problems.push(format!(
+                    "Task {id} is blocked by task {}, which doesn't exist.",
+                    tid_to_string(*blocker)
+                ));
+            }
+        }
+    }
+
+    let mut group_names = model.groups.keys().collect::<Vec<&String>>();
+    group_names.sort_unstable();
+    for name in group_names {
+        let group = model.groups.get(name).unwrap();
+        if group.name != *name {
+            problems.push(format!(
+                "Group `{name}` is stored under the name `{}`.",
+                group.name
+            ));
+        }
+        if group.tids.is_empty() {
+            problems.push(format!("Group `{name}` is empty."));
+        }
+        for (i, tid) in group.tids.iter().enumerate() {
+            let id = tid_to_string(*tid);
+            if group.tids[..i].contains(tid) {
+                problems.push(format!("Group `{name}` lists task {id} more than once."));
+            }
+            match model.tasks.get(tid) {
+                None => problems.push(format!(
+                    "Group `{name}` lists task {id}, which doesn't exist."
+                )),
+                Some(task) if task.group != *name => problems.push(format!(
+                    "Group `{name}` lists task {id}, which is in group `{}`.",
+                    task.group
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+
+    let mut visited: HashSet<TID> = HashSet::new();
+    for tid in tids.iter() {
+        let mut path: Vec<TID> = Vec::new();
+        if !visited.contains(tid) && has_cycle(*tid, &mut path, &mut visited, model) {
+            problems.push(format!(
+                "Task {} is part of a dependency cycle.",
+                tid_to_string(*tid)
+            ));
+        }
+    }
+
+    if let Some(tid) = tids.iter().find(|tid| **tid >= model.next_tid) {
+        problems.push(format!(
+            "The id of the next new task ({}) is not above the id of task {}.",
+            tid_to_string(model.next_tid),
+            tid_to_string(*tid)
+        ));
+    }
+
+    problems
+}
+
+fn process_cmd_checkdata(out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let model = read_model()?;
+    let problems = find_problems(&model);
+    if !problems.is_empty() {
+        return Err(Error::InvalidData(problems));
+    }
+    out.message("The data file is valid.");
+    Ok(())
+}
+
+fn process_cmd_config(out: &mut Output) -> Result<(), Error> {
+    out.config(config());
+    Ok(())
+}
+
+/// Opens `$VISUAL` or `$EDITOR` (falling back to `vi`) on a temporary file with
+/// the given content and returns the edited content.
+fn edit_in_editor(content: &str, file_name: &str) -> Result<String, Error> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(file_name);
+    // the file must be new, so that a file or symlink planted in a shared
+    // temporary directory isn't written through
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|_| Error::CannotWriteNotesFile)?;
+    let written = file.write_all(content.as_bytes());
+    drop(file);
+    if written.is_err() {
+        let _ = std::fs::remove_file(&path);
+        return Err(Error::CannotWriteNotesFile);
+    }
+
+    // the editor variable may contain arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or(Error::EditorFailed(editor.clone()))?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    match status {
+        Ok(status) if status.success() => edited.map_err(|_| Error::EditorFailed(editor)),
+        _ => Err(Error::EditorFailed(editor)),
+    }
+}
+
+fn process_cmd_notes(cmd: NotesCommand, out: &mut Output) -> Result<(), Error> {
+    let tid = cmd.tid;
+    let notes = {
+        let model = read_model()?;
+        let task = model
+            .tasks
+            .get(&tid)
+            .ok_or(Error::InvalidTID(tid_to_string(tid)))?;
+        task.notes.clone().unwrap_or_default()
+    };
+
+    // don't hold the lock while the editor is open
+    let file_name = format!("todo-notes-{}-{tid}.md", std::process::id());
+    let edited = edit_in_editor(&notes, &file_name)?;
+    let edited = edited.trim_end();
+    if edited == notes.trim_end() {
+        out.message("\nNotes unchanged.");
+        return Ok(());
+    }
+
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+    let task = model
+        .tasks
+        .get_mut(&tid)
+        .ok_or(Error::InvalidTID(tid_to_string(tid)))?;
+    task.notes = if edited.trim().is_empty() {
+        None
+    } else {
+        Some(edited.to_string())
+    };
+    save_model(&mut model, before, format!("notes of task {}", tid_to_string(tid)), out)?;
+
+    out.message("\nUpdated notes.");
+    out.task_details(model.tasks.get(&tid).unwrap(), &model);
+
+    Ok(())
+}
+
+fn process_cmd_show(cmd: ShowCommand, out: &mut Output) -> Result<(), Error> {
+    let model = read_model()?;
+    let task = model
+        .tasks
+        .get(&cmd.tid)
+        .ok_or(Error::InvalidTID(tid_to_string(cmd.tid)))?;
+    out.task_details(task, &model);
+    Ok(())
+}
+
+fn process_cmd_find(cmd: FindCommand, out: &mut Output) -> Result<(), Error> {
+    let model = read_model()?;
+    let matches = search(&model, &cmd.query, cmd.mode)?;
+    out.search(&cmd.query, &matches, &model);
+    Ok(())
+}
+
+fn process_cmd_archive(cmd: ArchiveCommand, out: &mut Output) -> Result<(), Error> {
+    let model = read_model()?;
+    out.archive(&model, cmd.query.as_deref());
+    Ok(())
+}
+
+fn process_cmd_restore(cmd: RestoreCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    for index in cmd.indices.iter() {
+        if *index >= model.archive.len() {
+            return Err(Error::InvalidArchiveIndex(index.to_string()));
+        }
+    }
+
+    // remove from the back so that the remaining indices stay valid
+    let mut indices = cmd.indices.clone();
+    indices.sort_unstable();
+    indices.dedup();
+    let mut restored: Vec<TID> = Vec::new();
+    for index in indices.into_iter().rev() {
+        let mut task = model.archive.remove(index).task;
+        // the id might have been given to a new task in the meantime
+        if model.tasks.contains_key(&task.tid) {
+            task.tid = new_tid(&mut model);
+        }
+        // blockers that were completed or deleted in the meantime are gone
+        task.blocked_by
+            .retain(|blocker| model.tasks.contains_key(blocker));
+        restored.push(task.tid);
+        insert_task(task, &mut model);
+    }
+    restored.reverse();
+    save_model(&mut model, before, format!("restore {}", format_tids(&restored)), out)?;
+
+    out.message(&format!("\nRestored tasks with ids {}.", format_tids(&restored)));
+    out.model(&model, &TaskFilter::default());
+
+    Ok(())
+}
+
+fn process_cmd_purge(cmd: PurgeCommand, out: &mut Output) -> Result<(), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    let cutoff = chrono::Local::now() - chrono::Duration::days(cmd.days.into());
+    let count = model.archive.len();
+    model.archive.retain(|entry| entry.completed >= cutoff);
+    let purged = count - model.archive.len();
+    save_model(&mut model, before, format!("purge archive older than {}d", cmd.days), out)?;
+
+    out.message(&format!(
+        "\nPurged {purged} archived task(s) completed more than {} day(s) ago.",
+        cmd.days
+    ));
+
+    Ok(())
+}
+
+fn process_cmd_export(cmd: ExportCommand, out: &mut Output) -> Result<(), Error> {
+    let model = read_model()?;
+    let groups = export_groups(&model, &cmd)?;
+    let content = match cmd.format {
+        ExportFormat::TodoTxt => todotxt::export(&groups),
+        ExportFormat::Markdown => markdown(&groups),
+        ExportFormat::Org => org(&groups),
+        ExportFormat::Ics => ics::export(&groups),
+        ExportFormat::Csv => spreadsheet::export(&groups),
+    };
+    out.export(&content);
+    Ok(())
+}
+
+/// Adds the tasks of a todo.txt file; completed ones go to the archive. Tasks
+/// with the same name in the same group as an existing one are skipped.
+/// Returns a summary.
+fn import_todotxt(text: &str, model: &mut Model) -> String {
+    let mut imported = 0;
+    let mut archived = 0;
+    let mut skipped = 0;
+    for entry in todotxt::import(text) {
+        let mut task = entry.task;
+        // groups with whitespace were exported with `_` instead
+        if let Some(group) = model.groups.keys().find(|name| todotxt::project(name) == task.group) {
+            task.group = group.clone();
+        }
+        let same = |other: &Task| other.name == task.name && other.group == task.group;
+        let exists = match entry.completed {
+            Some(_) => model.archive.iter().any(|archived| same(&archived.task)),
+            None => model.tasks.values().any(same),
+        };
+        if exists {
+            skipped += 1;
+            continue;
+        }
+        task.tid = new_tid(model);
+        match entry.completed {
+            Some(completed) => {
+                model.archive.push(ArchivedTask {
+                    task,
+                    completed,
+                    extra: Map::new(),
+                });
+                archived += 1;
+            }
+            None => insert_task(task, model),
+        }
+        imported += 1;
+    }
+    format!(
+        "\nImported {imported} task(s), {archived} of them into the archive. Skipped {skipped} task(s) that exist already."
+    )
+}
+
+/// Splits the categories of a VTODO into the group and the tags.
+fn split_categories(mut categories: Vec<String>) -> (Option<String>, Vec<String>) {
+    if categories.is_empty() {
+        return (None, Vec::new());
+    }
+    let group = categories.remove(0);
+    let mut tags: Vec<String> = Vec::new();
+    for category in categories {
+        if category != group && !tags.contains(&category) {
+            tags.push(category);
+        }
+    }
+    (Some(group), tags)
+}
+
+/// Creates or updates tasks from the VTODOs of an iCalendar file, matched by
+/// their UID. Properties that are missing leave a task as it is. Returns a
+/// summary.
+fn import_ics(text: &str, model: &mut Model) -> Result<String, Error> {
+    let mut created = 0;
+    let mut updated = 0;
+    let mut skipped = 0;
+    for todo in ics::import(text)? {
+        let has_uid = |task: &Task| todo.uid.as_ref().is_some_and(|uid| ics::task_uid(task) == *uid);
+        // tasks that are done already stay in the archive
+        if model.archive.iter().any(|entry| has_uid(&entry.task)) {
+            skipped += 1;
+            continue;
+        }
+        let existing = model.tasks.values().find(|task| has_uid(task)).map(|task| task.tid);
+        let (group, tags) = split_categories(todo.categories);
+
+        let Some(tid) = existing else {
+            let Some(name) = todo.summary else {
+                skipped += 1;
+                continue;
+            };
+            let tid = new_tid(model);
+            let mut extra = Map::new();
+            if let Some(uid) = todo.uid.filter(|uid| ics::uid_tid(uid) != Some(tid)) {
+                // importing the file again updates this task
+                extra.insert(ics::UID_KEY.to_string(), serde_json::Value::String(uid));
+            }
+            let task = Task {
+                tid,
+                name,
+                prio: todo.prio.unwrap_or(config().default_prio),
+                date: todo.created.unwrap_or_else(chrono::Local::now),
+                group: group.unwrap_or_else(|| config().default_group.clone()),
+                due: todo.due,
+                recurrence: None,
+                tags,
+                notes: todo.description,
+                checklist: Vec::new(),
+                blocked_by: Vec::new(),
+                extra,
+            };
+            match todo.completed {
+                Some(completed) => model.archive.push(ArchivedTask {
+                    task,
+                    completed,
+                    extra: Map::new(),
+                }),
+                None => insert_task(task, model),
+            }
+            created += 1;
+            continue;
+        };
+
+        let old = serde_json::to_value(model.tasks.get(&tid)).ok();
+        let task = model.tasks.get_mut(&tid).unwrap();
+        if let Some(summary) = todo.summary {
+            task.name = summary;
+        }
+        if let Some(prio) = todo.prio {
+            task.prio = prio;
+        }
+        if let Some(due) = todo.due {
+            task.due = Some(due);
+        }
+        if let Some(description) = todo.description {
+            task.notes = Some(description);
+        }
+        if let Some(group) = group {
+            task.tags = tags;
+            move_task(tid, model, &group);
+        }
+        let task = model.tasks.get(&tid).unwrap();
+        let group = model.groups.get_mut(&task.group).unwrap();
+        sort_tids(&mut group.tids, &model.tasks);
+        let changed = serde_json::to_value(model.tasks.get(&tid)).ok() != old;
+        if todo.completed.is_some() {
+            complete_task(tid, model);
+        }
+        if changed || todo.completed.is_some() {
+            updated += 1;
+        }
+    }
+    Ok(format!(
+        "\nCreated {created} task(s) and updated {updated}. Skipped {skipped} task(s) that are done already or have no summary."
+    ))
+}
+
+/// Creates or updates tasks from the rows of a CSV file. Rows with the id of
+/// an open task update it, other rows create new tasks. Returns a summary.
+fn import_csv(text: &str, model: &mut Model) -> Result<String, Error> {
+    let mut created = 0;
+    let mut updated = 0;
+    let mut skipped = 0;
+    for row in spreadsheet::import(text)? {
+        let existing = row.tid.filter(|tid| model.tasks.contains_key(tid));
+        let Some(tid) = existing else {
+            // rows of archived tasks, e.g. from `--include-done`
+            if row.tid.is_some_and(|tid| model.archive.iter().any(|entry| entry.task.tid == tid)) {
+                skipped += 1;
+                continue;
+            }
+            let name = row.name.ok_or_else(|| {
+                Error::InvalidImportFile(format!("line {}: a new task needs a name", row.line))
+            })?;
+            let task = Task {
+                tid: new_tid(model),
+                name,
+                prio: row.prio.unwrap_or(config().default_prio),
+                date: row.created.unwrap_or_else(chrono::Local::now),
+                group: row.group.unwrap_or_else(|| config().default_group.clone()),
+                due: row.due.flatten(),
+                recurrence: None,
+                tags: row.tags.unwrap_or_default(),
+                notes: None,
+                checklist: Vec::new(),
+                blocked_by: Vec::new(),
+                extra: Map::new(),
+            };
+            match row.done {
+                Some(completed) => model.archive.push(ArchivedTask {
+                    task,
+                    completed,
+                    extra: Map::new(),
+                }),
+                None => insert_task(task, model),
+            }
+            created += 1;
+            continue;
+        };
+
+        let old = serde_json::to_value(model.tasks.get(&tid)).ok();
+        let task = model.tasks.get_mut(&tid).unwrap();
+        if let Some(name) = row.name {
+            task.name = name;
+        }
+        if let Some(prio) = row.prio {
+            task.prio = prio;
+        }
+        if let Some(due) = row.due {
+            task.due = due;
+        }
+        if let Some(tags) = row.tags {
+            task.tags = tags;
+        }
+        if let Some(group) = row.group {
+            move_task(tid, model, &group);
+        }
+        let changed = serde_json::to_value(model.tasks.get(&tid)).ok() != old;
+        if row.done.is_some() {
+            complete_task(tid, model);
+        }
+        if changed || row.done.is_some() {
+            updated += 1;
+        }
+    }
+    // prios and due dates may have changed, and moved tasks were appended
+    for group in model.groups.values_mut() {
+        sort_tids(&mut group.tids, &model.tasks);
+    }
+    Ok(format!(
+        "\nCreated {created} task(s) and updated {updated}. Skipped {skipped} archived task(s)."
+    ))
+}
+
+/// Describes how a task changed, e.g. `prio B → A, group work → home`.
+fn describe_update(old: &Task, new: &Task) -> Vec<String> {
+    let due = |task: &Task| match &task.due {
+        Some(due) => due.format(&config().date_format).to_string(),
+        None => "none".to_string(),
+    };
+    let tags = |task: &Task| {
+        if task.tags.is_empty() {
+            "none".to_string()
+        } else {
+            task.tags.iter().map(|tag| format!("+{tag}")).collect::<Vec<String>>().join(" ")
+        }
+    };
+    let mut changes: Vec<String> = Vec::new();
+    if old.name != new.name {
+        changes.push(format!("name → `{}`", new.name));
+    }
+    if old.group != new.group {
+        changes.push(format!("group {} → {}", old.group, new.group));
+    }
+    if old.prio != new.prio {
+        changes.push(format!("prio {} → {}", prio_letter(old.prio), prio_letter(new.prio)));
+    }
+    if old.due != new.due {
+        changes.push(format!("due {} → {}", due(old), due(new)));
+    }
+    if old.tags != new.tags {
+        changes.push(format!("tags {} → {}", tags(old), tags(new)));
+    }
+    if old.notes != new.notes {
+        changes.push("notes".to_string());
+    }
+    changes
+}
+
+/// Lists what an import changes, task by task.
+fn print_import_diff(before: &Model, after: &Model, out: &mut Output) {
+    let mut tids = before
+        .tasks
+        .keys()
+        .chain(after.tasks.keys())
+        .copied()
+        .collect::<Vec<TID>>();
+    tids.sort_unstable();
+    tids.dedup();
+
+    let mut lines: Vec<String> = Vec::new();
+    for tid in tids {
+        let id = format!("[{}]", tid_to_string(tid));
+        match (before.tasks.get(&tid), after.tasks.get(&tid)) {
+            (None, Some(task)) => lines.push(format!(
+                "  {} {id} {} ({}, prio {})",
+                "+".green(),
+                task.name,
+                task.group,
+                prio_letter(task.prio)
+            )),
+            (Some(old), Some(new)) => {
+                let changes = describe_update(old, new);
+                if !changes.is_empty() {
+                    lines.push(format!("  {} {id} {}: {}", "~".yellow(), old.name, changes.join(", ")));
+                }
+            }
+            (Some(old), None) => lines.push(format!("  {} {id} {} (done)", "x".dimmed(), old.name)),
+            (None, None) => {}
+        }
+    }
+    for entry in after.archive.iter().skip(before.archive.len()) {
+        if !before.tasks.contains_key(&entry.task.tid) {
+            let id = format!("[{}]", tid_to_string(entry.task.tid));
+            lines.push(format!("  {} {id} {} (done)", "+".green(), entry.task.name));
+        }
+    }
+
+    if lines.is_empty() {
+        out.message("\nNo changes.");
+    } else {
+        out.message(&format!("\nChanges:\n\n{}", lines.join("\n")));
+    }
+}
+
+fn process_cmd_import(cmd: ImportCommand, out: &mut Output) -> Result<(), Error> {
+    let text = std::fs::read_to_string(&cmd.path)
+        .map_err(|_| Error::CannotReadImportFile(cmd.path.clone()))?;
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+
+    let summary = match cmd.format {
+        ImportFormat::TodoTxt => import_todotxt(&text, &mut model),
+        ImportFormat::Ics => import_ics(&text, &mut model)?,
+        ImportFormat::Csv => import_csv(&text, &mut model)?,
+    };
+    print_import_diff(&before, &model, out);
+    if cmd.dry_run {
+        out.changes(&before, &model);
+        out.message(&summary);
+        out.message("\nThis was a dry run, nothing was changed.");
+        return Ok(());
+    }
+    save_model(&mut model, before, format!("import {}", cmd.path), out)?;
 
-    println!("\nDeleted group successfully.");
-    println!("{}", PrintModel::new(&model, None));
+    out.message(&summary);
+    out.model(&model, &TaskFilter::default());
 
     Ok(())
 }
 
-pub fn process_command(command: Command) -> Result<(), Error> {
+pub fn process_command(command: Command, out: &mut Output) -> Result<(), Error> {
     match command {
-        Command::New(cmd) => process_cmd_new(cmd),
-        Command::List(cmd) => process_cmd_list(cmd),
-        Command::Done(cmd) => process_cmd_done(cmd),
-        Command::Update(cmd) => process_cmd_update(cmd),
-        Command::DeleteGroup(cmd) => process_cmd_deletegroup(cmd),
-        Command::Move(cmd) => process_cmd_move(cmd),
+        Command::Undo => process_cmd_undo(out),
+        Command::Redo => process_cmd_redo(out),
+        Command::CheckData => process_cmd_checkdata(out),
+        Command::Config => process_cmd_config(out),
+        Command::Find(cmd) => process_cmd_find(cmd, out),
+        Command::New(cmd) => process_cmd_new(cmd, out),
+        Command::List(cmd) => process_cmd_list(cmd, out),
+        Command::Done(cmd) => process_cmd_done(cmd, out),
+        Command::Update(cmd) => process_cmd_update(cmd, out),
+        Command::DeleteGroup(cmd) => process_cmd_deletegroup(cmd, out),
+        Command::RenameGroup(cmd) => process_cmd_renamegroup(cmd, out),
+        Command::OrderGroups(cmd) => process_cmd_ordergroups(cmd, out),
+        Command::Move(cmd) => process_cmd_move(cmd, out),
+        Command::Notes(cmd) => process_cmd_notes(cmd, out),
+        Command::Show(cmd) => process_cmd_show(cmd, out),
+        Command::Archive(cmd) => process_cmd_archive(cmd, out),
+        Command::Restore(cmd) => process_cmd_restore(cmd, out),
+        Command::Purge(cmd) => process_cmd_purge(cmd, out),
+        Command::Tui => run_tui(),
+        Command::Export(cmd) => process_cmd_export(cmd, out),
+        Command::Import(cmd) => process_cmd_import(cmd, out),
     }
 }
@@ -0,0 +1,103 @@
+use std::ops::Range;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::{Regex, RegexBuilder};
+
+use crate::display::ordered_groups;
+use crate::model::*;
+
+/// A task that matches a search, together with the parts that matched.
+pub struct SearchMatch<'a> {
+    pub task: &'a Task,
+    /// higher is better; only fuzzy searches rank their matches
+    pub score: i64,
+    /// byte ranges of the name that matched
+    pub name_spans: Vec<Range<usize>>,
+    /// the first line of the notes that matched, with the byte ranges that matched
+    pub notes_line: Option<(&'a str, Vec<Range<usize>>)>,
+}
+
+enum Matcher {
+    Pattern(Regex),
+    Fuzzy(Box<SkimMatcherV2>, String),
+}
+
+impl Matcher {
+    fn new(query: &str, mode: SearchMode) -> Result<Self, Error> {
+        let pattern = match mode {
+            SearchMode::Substring => regex::escape(query),
+            SearchMode::Regex => query.to_string(),
+            SearchMode::Fuzzy => {
+                let matcher = Box::new(SkimMatcherV2::default().ignore_case());
+                return Ok(Matcher::Fuzzy(matcher, query.to_string()));
+            }
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|_| Error::InvalidRegex(query.to_string()))?;
+        Ok(Matcher::Pattern(regex))
+    }
+
+    /// Returns the score and the matching byte ranges of `text`.
+    fn find(&self, text: &str) -> Option<(i64, Vec<Range<usize>>)> {
+        match self {
+            Matcher::Pattern(regex) => {
+                let spans = regex
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.range())
+                    .collect::<Vec<Range<usize>>>();
+                (!spans.is_empty()).then_some((0, spans))
+            }
+            Matcher::Fuzzy(matcher, query) => {
+                let (score, indices) = matcher.fuzzy_indices(text, query)?;
+                // the indices count characters, not bytes
+                let spans = text
+                    .char_indices()
+                    .enumerate()
+                    .filter(|(i, _)| indices.contains(i))
+                    .map(|(_, (start, c))| start..start + c.len_utf8())
+                    .collect();
+                Some((score, spans))
+            }
+        }
+    }
+}
+
+/// Searches the names and notes of all tasks. The matches are in the order of
+/// a listing; fuzzy matches are ranked by their score instead. Fuzzy searches
+/// only look at the names.
+pub fn search<'a>(model: &'a Model, query: &str, mode: SearchMode) -> Result<Vec<SearchMatch<'a>>, Error> {
+    let matcher = Matcher::new(query, mode)?;
+    let mut matches: Vec<SearchMatch<'a>> = Vec::new();
+    for group in ordered_groups(model) {
+        for tid in group.tids.iter() {
+            let task = model.tasks.get(tid).unwrap();
+            let name = matcher.find(&task.name);
+            let notes_line = match mode {
+                SearchMode::Fuzzy => None,
+                _ => task.notes.as_deref().and_then(|notes| {
+                    notes
+                        .lines()
+                        .find_map(|line| matcher.find(line).map(|(_, spans)| (line, spans)))
+                }),
+            };
+            if name.is_none() && notes_line.is_none() {
+                continue;
+            }
+            let (score, name_spans) = name.unwrap_or_default();
+            matches.push(SearchMatch {
+                task,
+                score,
+                name_spans,
+                notes_line,
+            });
+        }
+    }
+    if let SearchMode::Fuzzy = mode {
+        matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    }
+    Ok(matches)
+}
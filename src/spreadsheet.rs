@@ -0,0 +1,143 @@
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::dates::*;
+use crate::export::*;
+use crate::model::*;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const COLUMNS: [&str; 8] = ["tid", "group", "prio", "name", "created", "due", "tags", "done"];
+
+/// A row of a CSV file. Columns that are missing are `None`, so that they
+/// leave the task as it is.
+pub struct CsvRow {
+    pub line: u64,
+    pub tid: Option<TID>,
+    pub group: Option<String>,
+    pub prio: Option<Prio>,
+    pub name: Option<String>,
+    pub created: Option<DateTime<Local>>,
+    /// `Some(None)` if the due date was cleared
+    pub due: Option<Option<DateTime<Local>>>,
+    pub tags: Option<Vec<String>>,
+    /// when the task was completed
+    pub done: Option<DateTime<Local>>,
+}
+
+/// Writes a header row and a row per task. Tags are separated by spaces.
+pub fn export(groups: &[ExportGroup]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(COLUMNS).unwrap();
+    for group in groups.iter() {
+        for entry in group.tasks.iter() {
+            let task = entry.task;
+            let date = |date: Option<&DateTime<Local>>| {
+                date.map(|date| date.format(DATE_FORMAT).to_string())
+                    .unwrap_or_default()
+            };
+            writer
+                .write_record([
+                    tid_to_string(task.tid),
+                    task.group.clone(),
+                    prio_letter(task.prio).to_string(),
+                    task.name.clone(),
+                    date(Some(&task.date)),
+                    date(task.due.as_ref()),
+                    task.tags.join(" "),
+                    date(entry.completed.as_ref()),
+                ])
+                .unwrap();
+        }
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+fn invalid(line: u64, message: &str) -> Error {
+    Error::InvalidImportFile(format!("line {line}: {message}"))
+}
+
+fn parse_prio(prio: &str) -> Option<Prio> {
+    match prio.to_lowercase().as_str() {
+        "a" => Some(Prio::A),
+        "b" => Some(Prio::B),
+        "c" => Some(Prio::C),
+        _ => None,
+    }
+}
+
+fn parse_date(date: &str, line: u64) -> Result<DateTime<Local>, Error> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .ok()
+        .and_then(start_of_day)
+        .ok_or_else(|| invalid(line, &format!("invalid date `{date}`, expected e.g. `2026-11-03`")))
+}
+
+/// Parses a CSV file with a header row. The columns are found by their
+/// names, so they can be in any order and columns can be left out.
+pub fn import(text: &str) -> Result<Vec<CsvRow>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid(1, &e.to_string()))?
+        .iter()
+        .map(|header| header.to_lowercase())
+        .collect::<Vec<String>>();
+    if !headers.iter().any(|header| COLUMNS.contains(&header.as_str())) {
+        return Err(invalid(
+            1,
+            &format!("expected a header row with the columns {}", COLUMNS.join(", ")),
+        ));
+    }
+
+    let mut rows: Vec<CsvRow> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid(e.position().map_or(0, |p| p.line()), &e.to_string()))?;
+        let line = record.position().map_or(0, |p| p.line());
+        let cell = |column: &str| {
+            let index = headers.iter().position(|header| header == column)?;
+            Some(record.get(index).unwrap_or_default())
+        };
+        let filled = |column: &str| cell(column).filter(|value| !value.is_empty());
+        if record.iter().all(|value| value.is_empty()) {
+            continue;
+        }
+
+        let tid = match filled("tid") {
+            Some(tid) => Some(tid_from_str(tid).ok_or_else(|| invalid(line, &format!("invalid id `{tid}`")))?),
+            None => None,
+        };
+        let prio = match filled("prio") {
+            Some(prio) => Some(parse_prio(prio).ok_or_else(|| invalid(line, &format!("invalid prio `{prio}`")))?),
+            None => None,
+        };
+        // due dates can also be given like `fri` or `in 3d`
+        let due = match cell("due") {
+            Some("") => Some(None),
+            Some(due) => Some(resolve_due(due).map_err(|_| invalid(line, &format!("invalid due date `{due}`")))?),
+            None => None,
+        };
+        rows.push(CsvRow {
+            line,
+            tid,
+            group: filled("group").map(|group| group.to_string()),
+            prio,
+            name: filled("name").map(|name| name.to_string()),
+            created: filled("created").map(|date| parse_date(date, line)).transpose()?,
+            due,
+            tags: cell("tags").map(|tags| {
+                let mut unique: Vec<String> = Vec::new();
+                for tag in tags.split_whitespace().map(|tag| tag.trim_start_matches('+')) {
+                    if !tag.is_empty() && !unique.iter().any(|t| t == tag) {
+                        unique.push(tag.to_string());
+                    }
+                }
+                unique
+            }),
+            done: filled("done").map(|date| parse_date(date, line)).transpose()?,
+        });
+    }
+    Ok(rows)
+}
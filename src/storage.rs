@@ -1,36 +1,341 @@
-use std::io::Write;
-use std::{fs::OpenOptions, path::PathBuf};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use crate::model::{Error, Model};
+use serde::Deserialize;
+use serde_json::{json, Value};
 
-fn get_storage_path() -> PathBuf {
+use crate::config::{config, resolve_config_path};
+use crate::model::{Error, GlobalOptions, Journal, Model};
+
+/// How many previous generations of the data file are kept around.
+const BACKUP_COUNT: usize = 3;
+
+/// How long to wait for another invocation to release the data file.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The version of the data file layout that this build writes. Version 1 is
+/// the bare model from before the layout was versioned.
+const DATA_VERSION: u32 = 2;
+
+/// Upgrades the data file from the version at the same index plus one to the
+/// next version.
+const MIGRATIONS: [fn(Value) -> Value; 1] = [wrap_model];
+
+/// The version of the data file that was read. A file written by a newer
+/// version keeps its version, so that the newer build doesn't migrate it again.
+static READ_VERSION: AtomicU32 = AtomicU32::new(DATA_VERSION);
+
+/// Whether this invocation holds the lock on the data file.
+static DATA_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// The versioned envelope around the model. New data belongs into the model,
+/// where unknown fields are preserved.
+#[derive(Deserialize)]
+struct DataFile {
+    version: u32,
+    model: Model,
+}
+
+/// Version 1 to 2: the model is wrapped in an envelope with a version, and the
+/// counter for task ids starts after the highest id in use.
+fn wrap_model(mut document: Value) -> Value {
+    if document.get("next_tid").is_none() {
+        let tids = document["tasks"]
+            .as_object()
+            .into_iter()
+            .flat_map(|tasks| tasks.values())
+            .chain(
+                document["archive"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|entry| &entry["task"]),
+            )
+            .filter_map(|task| task["tid"].as_u64());
+        let next_tid = tids.max().map_or(0, |tid| tid + 1);
+        if let Some(model) = document.as_object_mut() {
+            model.insert("next_tid".to_string(), json!(next_tid));
+        }
+    }
+    json!({ "version": 2, "model": document })
+}
+
+fn data_version(document: &Value) -> u32 {
+    match document.get("version") {
+        Some(version) => version.as_u64().map_or(0, |version| version as u32),
+        None => 1,
+    }
+}
+
+/// The name of a data file that belongs to a directory and its subdirectories.
+const LOCAL_DATA_FILE: &str = ".todo.json";
+
+/// The data file chosen by `select_data_file`.
+static STORAGE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn get_default_storage_path() -> PathBuf {
     directories::BaseDirs::new()
         .data_dir()
         .join("todotui-data.json")
 }
 
+fn get_storage_path() -> PathBuf {
+    STORAGE_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(get_default_storage_path)
+}
+
+/// Looks for `.todo.json` in the current directory and its parents.
+fn find_local_data_file() -> Option<PathBuf> {
+    let dir = std::env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|dir| dir.join(LOCAL_DATA_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Decides which data file is used. In order of precedence: `--file`,
+/// `--profile`, the `TODO_FILE` environment variable, a `.todo.json` in the
+/// current directory or a parent, and finally the default data file.
+pub fn select_data_file(options: &GlobalOptions) -> Result<(), Error> {
+    let path = if let Some(ref file) = options.file {
+        PathBuf::from(file)
+    } else if let Some(ref profile) = options.profile {
+        let file = config()
+            .profiles
+            .get(profile)
+            .ok_or(Error::UnknownProfile(profile.to_string()))?;
+        resolve_config_path(file)
+    } else if let Some(file) = std::env::var_os("TODO_FILE").filter(|file| !file.is_empty()) {
+        PathBuf::from(file)
+    } else if let Some(path) = find_local_data_file() {
+        path
+    } else {
+        return Ok(());
+    };
+    // the sidecar files need a parent directory and a file name to extend
+    let path = std::path::absolute(&path).unwrap_or(path);
+    if path.is_dir() || path.file_name().is_none() {
+        return Err(Error::InvalidDataFilePath(path.display().to_string()));
+    }
+    let _ = STORAGE_PATH.set(path);
+    Ok(())
+}
+
+/// Returns the path of a file that lives next to `path`, e.g.
+/// `todotui-data.json.tmp` for the suffix `tmp`.
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    sidecar_path(path, &format!("bak.{generation}"))
+}
+
+/// Parses the data file and upgrades older layouts. Newer layouts are read as
+/// far as this build understands them; unknown fields are kept in the model.
+fn parse_data_file(json: &str) -> Result<DataFile, Error> {
+    let mut document = serde_json::from_str::<Value>(json).map_err(|_| Error::InvalidDataFile)?;
+    let version = data_version(&document);
+    if version == 0 {
+        return Err(Error::InvalidDataFile);
+    }
+    for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+        document = migration(document);
+    }
+    let mut data = serde_json::from_value::<DataFile>(document).map_err(|_| Error::InvalidDataFile)?;
+    data.version = version.max(DATA_VERSION);
+    Ok(data)
+}
+
+fn parse_model(json: &str) -> Result<Model, Error> {
+    let data = parse_data_file(json)?;
+    READ_VERSION.store(data.version, Ordering::Relaxed);
+    Ok(data.model)
+}
+
 pub fn read_model() -> Result<Model, Error> {
     let path = get_storage_path();
     if path.exists() {
-        let json = std::fs::read_to_string(path).map_err(|_| Error::CannotReadDataFile)?;
-        let model = serde_json::from_str::<Model>(&json).map_err(|_| Error::InvalidDataFile)?;
-
-        Ok(model)
+        let json = std::fs::read_to_string(&path).map_err(|_| Error::CannotReadDataFile)?;
+        match parse_model(&json) {
+            Ok(model) => Ok(model),
+            Err(e) => offer_restore(&path).ok_or(e),
+        }
     } else {
         Ok(Model::default())
     }
 }
 
-pub fn write_model(model: &Model) -> Result<(), Error> {
-    let json = serde_json::to_string_pretty(model).map_err(|_| Error::SerializationError)?;
-    let path = get_storage_path();
+/// Looks for the newest backup that can be parsed and asks the user whether it
+/// should replace the broken data file. Only asks if stdin is a terminal. The
+/// restore happens under the lock, also for commands that only read.
+fn offer_restore(path: &Path) -> Option<Model> {
+    let (backup, json, model) = (1..=BACKUP_COUNT).find_map(|generation| {
+        let backup = backup_path(path, generation);
+        let json = std::fs::read_to_string(&backup).ok()?;
+        let model = parse_model(&json).ok()?;
+        Some((backup, json, model))
+    })?;
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "A backup of the data file is available at `{}`.",
+            backup.display()
+        );
+        return None;
+    }
+
+    eprint!(
+        "Data file `{}` has the wrong format. Restore the backup `{}`? [y/N] ",
+        path.display(),
+        backup.display()
+    );
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return None;
+    }
+
+    let _lock = if DATA_LOCKED.load(Ordering::Relaxed) {
+        None
+    } else {
+        let lock = lock_data_file().map_err(|e| eprintln!("{e}")).ok()?;
+        // another invocation might have repaired or replaced the file meanwhile
+        let current = std::fs::read_to_string(path).ok()?;
+        if let Ok(model) = parse_model(&current) {
+            return Some(model);
+        }
+        Some(lock)
+    };
+    // the broken file ends up as the newest backup, so nothing is lost
+    rotate_backups(path);
+    if let Err(e) = write_atomically(path, &json) {
+        eprintln!("{e}");
+        return None;
+    }
+    Some(model)
+}
+
+/// Shifts the backups by one generation and copies the current data file
+/// into the first slot. The oldest backup is dropped.
+fn rotate_backups(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    for generation in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(path, generation);
+        if from.exists() {
+            let _ = std::fs::rename(&from, backup_path(path, generation + 1));
+        }
+    }
+    let _ = std::fs::copy(path, backup_path(path, 1));
+}
+
+/// Writes `content` to a temporary file in the same directory, flushes it to
+/// disk and renames it over `path`, so that `path` is never half-written.
+fn write_atomically(path: &Path, content: &str) -> Result<(), Error> {
+    let dir = path.parent().unwrap();
+    std::fs::create_dir_all(dir).map_err(|_| Error::CannotWriteOpenDataFile)?;
+
+    let tmp_path = sidecar_path(path, "tmp");
     let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
-        .open(path)
+        .open(&tmp_path)
         .map_err(|_| Error::CannotWriteOpenDataFile)?;
-    write!(file, "{}", json).map_err(|_| Error::CannotWriteDataFile)?;
+    let written = file
+        .write_all(content.as_bytes())
+        .and_then(|_| file.sync_all());
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(Error::CannotWriteDataFile);
+    }
+    drop(file);
+
+    std::fs::rename(&tmp_path, path).map_err(|_| Error::CannotWriteDataFile)?;
+    // make the rename itself durable; not every platform can open directories
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
 
     Ok(())
 }
+
+pub fn write_model(model: &Model) -> Result<(), Error> {
+    let data = json!({
+        "version": READ_VERSION.load(Ordering::Relaxed),
+        "model": model,
+    });
+    let json = serde_json::to_string_pretty(&data).map_err(|_| Error::SerializationError)?;
+    let path = get_storage_path();
+    rotate_backups(&path);
+    write_atomically(&path, &json)
+}
+
+fn get_journal_path() -> PathBuf {
+    sidecar_path(&get_storage_path(), "journal")
+}
+
+pub fn read_journal() -> Result<Journal, Error> {
+    let path = get_journal_path();
+    if path.exists() {
+        let json = std::fs::read_to_string(path).map_err(|_| Error::CannotReadJournalFile)?;
+        serde_json::from_str::<Journal>(&json).map_err(|_| Error::InvalidJournalFile)
+    } else {
+        Ok(Journal::default())
+    }
+}
+
+pub fn write_journal(journal: &Journal) -> Result<(), Error> {
+    let json = serde_json::to_string(journal).map_err(|_| Error::SerializationError)?;
+    write_atomically(&get_journal_path(), &json)
+}
+
+/// An exclusive advisory lock on the data file. The lock is held until the
+/// value is dropped.
+pub struct DataLock {
+    _file: File,
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        DATA_LOCKED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Locks the data file (via a sidecar lock file) for a read-modify-write
+/// cycle. Waits at most `LOCK_TIMEOUT` for other invocations to finish.
+pub fn lock_data_file() -> Result<DataLock, Error> {
+    let path = get_storage_path();
+    std::fs::create_dir_all(path.parent().unwrap()).map_err(|_| Error::CannotLockDataFile)?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sidecar_path(&path, "lock"))
+        .map_err(|_| Error::CannotLockDataFile)?;
+
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => {
+                DATA_LOCKED.store(true, Ordering::Relaxed);
+                return Ok(DataLock { _file: file });
+            }
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => {
+                std::thread::sleep(LOCK_POLL_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => return Err(Error::DataFileLocked),
+            Err(TryLockError::Error(_)) => return Err(Error::CannotLockDataFile),
+        }
+    }
+}
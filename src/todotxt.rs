@@ -0,0 +1,161 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde_json::Map;
+
+use crate::config::config;
+use crate::dates::*;
+use crate::export::*;
+use crate::model::*;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A line of a todo.txt file. The task gets its id once it is imported.
+pub struct TodoTxtEntry {
+    pub task: Task,
+    /// when the task was completed, for lines starting with `x`
+    pub completed: Option<DateTime<Local>>,
+}
+
+/// todo.txt has the priorities `A` to `Z`; everything below `B` is `c`.
+fn parse_prio_letter(letter: &str) -> Option<Prio> {
+    match letter {
+        "A" => Some(Prio::A),
+        "B" => Some(Prio::B),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Prio::C),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+/// Projects can't contain whitespace, so it is replaced with `_`.
+pub fn project(group: &str) -> String {
+    group.split_whitespace().collect::<Vec<&str>>().join("_")
+}
+
+/// Formats a task like `(A) 2026-10-01 call mom +family @phone due:2026-10-20`.
+/// Completed tasks start with `x` and their completion date instead and keep
+/// their prio as `pri:A`.
+fn format_task(entry: &ExportTask) -> String {
+    let task = entry.task;
+    let mut words: Vec<String> = Vec::new();
+    match &entry.completed {
+        Some(completed) => {
+            words.push("x".to_string());
+            words.push(completed.format(DATE_FORMAT).to_string());
+        }
+        None => words.push(format!("({})", prio_letter(task.prio))),
+    }
+    words.push(task.date.format(DATE_FORMAT).to_string());
+    words.push(task.name.clone());
+    words.push(format!("+{}", project(&task.group)));
+    words.extend(task.tags.iter().map(|tag| format!("@{tag}")));
+    if let Some(due) = &task.due {
+        words.push(format!("due:{}", due.format(DATE_FORMAT)));
+    }
+    if entry.completed.is_some() {
+        words.push(format!("pri:{}", prio_letter(task.prio)));
+    }
+    words.join(" ")
+}
+
+/// Writes one task per line. The group becomes the project and the tags
+/// become contexts.
+pub fn export(groups: &[ExportGroup]) -> String {
+    let mut text = String::new();
+    for group in groups.iter() {
+        for entry in group.tasks.iter() {
+            text.push_str(&format_task(entry));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Parses a line like `x 2026-10-18 2026-10-01 call mom +family @phone`.
+/// The first project is the group, further projects and the contexts become
+/// tags. Returns `None` for lines without a name.
+fn parse_line(line: &str) -> Option<TodoTxtEntry> {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    let mut rest = words.as_slice();
+
+    let mut completed = None;
+    if let ["x", tail @ ..] = rest {
+        rest = tail;
+        completed = Some(Local::now());
+        if let Some(date) = rest.first().and_then(|word| parse_date(word)) {
+            completed = start_of_day(date).or(completed);
+            rest = &rest[1..];
+        }
+    }
+    let mut prio = None;
+    if let Some(letter) = rest.first().and_then(|word| word.strip_prefix('(')?.strip_suffix(')')) {
+        if let Some(letter) = parse_prio_letter(letter) {
+            prio = Some(letter);
+            rest = &rest[1..];
+        }
+    }
+    let mut created = None;
+    if let Some(date) = rest.first().and_then(|word| parse_date(word)) {
+        created = start_of_day(date);
+        rest = &rest[1..];
+    }
+
+    let mut name: Vec<&str> = Vec::new();
+    let mut projects: Vec<String> = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut due = None;
+    for word in rest.iter() {
+        let project = word.strip_prefix('+').filter(|project| !project.is_empty());
+        let context = word.strip_prefix('@').filter(|context| !context.is_empty());
+        if let Some(project) = project {
+            projects.push(project.to_string());
+        } else if let Some(context) = context {
+            if !tags.iter().any(|tag| tag == context) {
+                tags.push(context.to_string());
+            }
+        } else if let Some(date) = word.strip_prefix("due:").and_then(parse_date) {
+            due = end_of_day(date);
+        } else if let Some(letter) = word.strip_prefix("pri:").and_then(parse_prio_letter) {
+            // completed tasks keep their priority this way
+            prio = prio.or(Some(letter));
+        } else {
+            name.push(word);
+        }
+    }
+    if name.is_empty() {
+        return None;
+    }
+    let group = if projects.is_empty() {
+        config().default_group.clone()
+    } else {
+        projects.remove(0)
+    };
+    for project in projects {
+        if !tags.contains(&project) {
+            tags.push(project);
+        }
+    }
+
+    let task = Task {
+        tid: 0,
+        name: name.join(" "),
+        prio: prio.unwrap_or(config().default_prio),
+        date: created.unwrap_or_else(Local::now),
+        group,
+        due,
+        recurrence: None,
+        tags,
+        notes: None,
+        checklist: Vec::new(),
+        blocked_by: Vec::new(),
+        extra: Map::new(),
+    };
+    Some(TodoTxtEntry { task, completed })
+}
+
+/// Parses a todo.txt file. Lines without a name are skipped.
+pub fn import(text: &str) -> Vec<TodoTxtEntry> {
+    text.lines().filter_map(parse_line).collect()
+}
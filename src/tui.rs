@@ -0,0 +1,456 @@
+use std::collections::HashSet;
+use std::io::IsTerminal;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::display::*;
+use crate::model::*;
+use crate::processing::*;
+use crate::storage::*;
+
+const HELP: &str =
+    "↑↓ select  ←→ fold  d done  a/b/c prio  m move  e edit  u undo  ^r redo  q quit";
+
+/// A line of the task list.
+#[derive(Clone, PartialEq)]
+enum Row {
+    Group(String),
+    Task(TID),
+}
+
+/// What the keys currently do.
+enum Mode {
+    Normal,
+    /// editing the name of a task inline
+    Edit(TID, String),
+    /// typing the group that a task is moved to
+    Move(TID, String),
+}
+
+enum Input {
+    Editing(String),
+    Confirmed(String),
+    Cancelled,
+}
+
+/// Applies a key to the text of a prompt.
+fn edit_input(mut text: String, key: KeyEvent) -> Input {
+    match key.code {
+        KeyCode::Enter => Input::Confirmed(text),
+        KeyCode::Esc => Input::Cancelled,
+        KeyCode::Backspace => {
+            text.pop();
+            Input::Editing(text)
+        }
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Input::Editing(String::new())
+        }
+        KeyCode::Char(c) => {
+            text.push(c);
+            Input::Editing(text)
+        }
+        _ => Input::Editing(text),
+    }
+}
+
+fn check_task(tid: TID, model: &Model) -> Result<(), Error> {
+    if model.tasks.contains_key(&tid) {
+        Ok(())
+    } else {
+        Err(Error::InvalidTID(tid_to_string(tid)))
+    }
+}
+
+/// Applies a change to the current content of the data file, which may have
+/// been changed by another invocation in the meantime, and records it in the
+/// journal. `apply` returns the message that is shown.
+fn apply_change(
+    action: String,
+    apply: impl FnOnce(&mut Model) -> Result<String, Error>,
+) -> Result<(Model, String), Error> {
+    let _lock = lock_data_file()?;
+    let mut model = read_model()?;
+    let before = model.clone();
+    let message = apply(&mut model)?;
+    record_change(&mut model, before, action)?;
+    Ok((model, message))
+}
+
+fn task_line(task: &Task, model: &Model, editing: Option<&str>) -> Line<'static> {
+    let blocked = task
+        .blocked_by
+        .iter()
+        .any(|blocker| model.tasks.contains_key(blocker));
+    let glyph = Span::raw(prio_glyph(task.prio));
+    let glyph = match task.prio {
+        Prio::A => glyph.red(),
+        Prio::B => glyph,
+        Prio::C => glyph.dim(),
+    };
+    let mut spans = vec![
+        Span::raw("  "),
+        glyph,
+        Span::raw(" "),
+        Span::raw(format!("[{}]", tid_to_string(task.tid))).dim(),
+        Span::raw(if blocked { " 🔒 " } else { " " }),
+    ];
+    match editing {
+        Some(text) => {
+            spans.push(Span::raw(text.to_string()).underlined());
+            spans.push(Span::raw(" ").reversed());
+        }
+        None if blocked => spans.push(Span::raw(task.name.clone()).dim()),
+        None => spans.push(Span::raw(task.name.clone())),
+    }
+    if !task.checklist.is_empty() {
+        let done = task.checklist.iter().filter(|item| item.done).count();
+        let progress = Span::raw(format!(" [{}/{}]", done, task.checklist.len()));
+        if done == task.checklist.len() {
+            spans.push(progress.green());
+        } else {
+            spans.push(progress.dim());
+        }
+    }
+    for tag in task.tags.iter() {
+        spans.push(Span::raw(format!(" +{tag}")).dim());
+    }
+    if task.recurrence.is_some() {
+        spans.push(Span::raw(" ↻").dim());
+    }
+    if task.notes.is_some() {
+        spans.push(Span::raw(" ✎").dim());
+    }
+    spans.push(Span::raw(format!("  {}", format_date(&task.date))).dim().italic());
+    if let Some(due) = &task.due {
+        let text = Span::raw(format!("  {}", format_due(due)));
+        spans.push(match due_urgency(due) {
+            Urgency::Overdue => text.red(),
+            Urgency::Soon => text.yellow(),
+            Urgency::Later => text.dim(),
+        });
+    }
+    Line::from(spans)
+}
+
+fn group_line(group: &Group, collapsed: bool) -> Line<'static> {
+    let marker = if collapsed { "▸" } else { "▾" };
+    Line::from(vec![
+        Span::raw(format!("{marker} {}", group.name)).bold(),
+        Span::raw(format!(" ({})", group.tids.len())).dim(),
+    ])
+}
+
+/// The full-screen interface. Every change is written right away, so the
+/// data file is only locked while a change is applied.
+struct App {
+    model: Model,
+    collapsed: HashSet<String>,
+    rows: Vec<Row>,
+    list: ListState,
+    mode: Mode,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(model: Model) -> Self {
+        let mut app = App {
+            model,
+            collapsed: HashSet::new(),
+            rows: Vec::new(),
+            list: ListState::default(),
+            mode: Mode::Normal,
+            status: String::new(),
+            quit: false,
+        };
+        app.update_rows();
+        app
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.list.selected().and_then(|i| self.rows.get(i)).cloned()
+    }
+
+    fn selected_task(&self) -> Option<TID> {
+        match self.selected_row() {
+            Some(Row::Task(tid)) => Some(tid),
+            _ => None,
+        }
+    }
+
+    /// Lists the groups in the order of `todo`. The selection stays on the
+    /// same row if it's still there.
+    fn update_rows(&mut self) {
+        let selected = self.selected_row();
+        let mut rows: Vec<Row> = Vec::new();
+        for group in ordered_groups(&self.model) {
+            rows.push(Row::Group(group.name.clone()));
+            if !self.collapsed.contains(&group.name) {
+                rows.extend(group.tids.iter().map(|tid| Row::Task(*tid)));
+            }
+        }
+        self.rows = rows;
+        let index = selected
+            .and_then(|row| self.rows.iter().position(|r| *r == row))
+            .or(self.list.selected())
+            .unwrap_or(0);
+        if self.rows.is_empty() {
+            self.list.select(None);
+        } else {
+            self.list.select(Some(index.min(self.rows.len() - 1)));
+        }
+    }
+
+    fn change(&mut self, action: String, apply: impl FnOnce(&mut Model) -> Result<String, Error>) {
+        match apply_change(action, apply) {
+            Ok((model, message)) => {
+                self.model = model;
+                self.status = message;
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+        self.update_rows();
+    }
+
+    fn complete(&mut self, tid: TID) {
+        self.change(format!("done {}", tid_to_string(tid)), |model| {
+            check_task(tid, model)?;
+            let completion = complete_task(tid, model);
+            Ok(match completion.next {
+                Some(next) => format!(
+                    "Task {} recurs, the next occurrence is task {}.",
+                    tid_to_string(tid),
+                    tid_to_string(next)
+                ),
+                None => format!("Moved task {} to the archive.", tid_to_string(tid)),
+            })
+        });
+    }
+
+    fn set_prio(&mut self, tid: TID, prio: Prio) {
+        self.change(format!("update task {}", tid_to_string(tid)), |model| {
+            check_task(tid, model)?;
+            let task = model.tasks.get_mut(&tid).unwrap();
+            task.prio = prio;
+            let group = model.groups.get_mut(&task.group).unwrap();
+            sort_tids(&mut group.tids, &model.tasks);
+            Ok(format!("Changed the prio of task {}.", tid_to_string(tid)))
+        });
+    }
+
+    fn rename(&mut self, tid: TID, name: String) {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            self.status = "The name of a task can't be empty.".to_string();
+            return;
+        }
+        self.change(format!("update task {}", tid_to_string(tid)), |model| {
+            check_task(tid, model)?;
+            model.tasks.get_mut(&tid).unwrap().name = name;
+            Ok(format!("Renamed task {}.", tid_to_string(tid)))
+        });
+    }
+
+    fn move_to(&mut self, tid: TID, group_name: String) {
+        let group_name = group_name.trim().to_string();
+        if group_name.is_empty() {
+            return;
+        }
+        // the moved task stays selected
+        self.collapsed.remove(&group_name);
+        self.change(format!("move {}", tid_to_string(tid)), |model| {
+            check_task(tid, model)?;
+            move_task(tid, model, &group_name);
+            let group = model.groups.get_mut(&group_name).unwrap();
+            sort_tids(&mut group.tids, &model.tasks);
+            Ok(format!("Moved task {} to {}.", tid_to_string(tid), group_name))
+        });
+    }
+
+    fn undo(&mut self) {
+        match lock_data_file().and_then(|_lock| undo_change()) {
+            Ok(undone) => {
+                self.model = undone.after;
+                self.status = format!("Undid `{}`.", undone.action);
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+        self.update_rows();
+    }
+
+    fn redo(&mut self) {
+        match lock_data_file().and_then(|_lock| redo_change()) {
+            Ok(redone) => {
+                self.model = redone.after;
+                self.status = format!("Redid `{}`.", redone.action);
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+        self.update_rows();
+    }
+
+    /// Collapses or expands the group of the selected row.
+    fn fold(&mut self, collapse: Option<bool>) {
+        let group_name = match self.selected_row() {
+            Some(Row::Group(name)) => name,
+            Some(Row::Task(tid)) => self.model.tasks.get(&tid).unwrap().group.clone(),
+            None => return,
+        };
+        let collapse = collapse.unwrap_or(!self.collapsed.contains(&group_name));
+        if collapse {
+            self.collapsed.insert(group_name.clone());
+            // the selected task is hidden now, so its group is selected instead
+            let index = self.rows.iter().position(|row| *row == Row::Group(group_name.clone()));
+            self.list.select(index);
+        } else {
+            self.collapsed.remove(&group_name);
+        }
+        self.update_rows();
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal_key(key),
+            Mode::Edit(tid, text) => match edit_input(text, key) {
+                Input::Editing(text) => self.mode = Mode::Edit(tid, text),
+                Input::Confirmed(text) => self.rename(tid, text),
+                Input::Cancelled => {}
+            },
+            Mode::Move(tid, text) if key.code == KeyCode::Tab => {
+                // completes the name of an existing group
+                let completed = ordered_groups(&self.model)
+                    .into_iter()
+                    .map(|group| group.name.clone())
+                    .find(|name| name.starts_with(&text))
+                    .unwrap_or(text);
+                self.mode = Mode::Move(tid, completed);
+            }
+            Mode::Move(tid, text) => match edit_input(text, key) {
+                Input::Editing(text) => self.mode = Mode::Move(tid, text),
+                Input::Confirmed(text) => self.move_to(tid, text),
+                Input::Cancelled => {}
+            },
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        self.status.clear();
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('r') => self.redo(),
+                KeyCode::Char('c') => self.quit = true,
+                _ => {}
+            }
+            return;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.list.select_last(),
+            KeyCode::Left | KeyCode::Char('h') => self.fold(Some(true)),
+            KeyCode::Right | KeyCode::Char('l') => self.fold(Some(false)),
+            KeyCode::Enter | KeyCode::Char(' ') => self.fold(None),
+            KeyCode::Char('u') => self.undo(),
+            _ => {
+                let Some(tid) = self.selected_task() else {
+                    return;
+                };
+                match key.code {
+                    KeyCode::Char('d') => self.complete(tid),
+                    KeyCode::Char('a') => self.set_prio(tid, Prio::A),
+                    KeyCode::Char('b') => self.set_prio(tid, Prio::B),
+                    KeyCode::Char('c') => self.set_prio(tid, Prio::C),
+                    KeyCode::Char('e') => {
+                        let name = self.model.tasks.get(&tid).unwrap().name.clone();
+                        self.mode = Mode::Edit(tid, name);
+                    }
+                    KeyCode::Char('m') => self.mode = Mode::Move(tid, String::new()),
+                    _ => {}
+                }
+            }
+        }
+        // `select_last` selects beyond the end until the list is rendered
+        if let Some(index) = self.list.selected() {
+            self.list.select(Some(index.min(self.rows.len().saturating_sub(1))));
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, status_area, help_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        if self.rows.is_empty() {
+            frame.render_widget(Paragraph::new("No todos."), list_area);
+        } else {
+            let items = self
+                .rows
+                .iter()
+                .map(|row| match row {
+                    Row::Group(name) => {
+                        let group = self.model.groups.get(name).unwrap();
+                        ListItem::new(group_line(group, self.collapsed.contains(name)))
+                    }
+                    Row::Task(tid) => {
+                        let task = self.model.tasks.get(tid).unwrap();
+                        let editing = match &self.mode {
+                            Mode::Edit(edited, text) if edited == tid => Some(text.as_str()),
+                            _ => None,
+                        };
+                        ListItem::new(task_line(task, &self.model, editing))
+                    }
+                })
+                .collect::<Vec<ListItem>>();
+            let list = List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut self.list);
+        }
+
+        let status = match &self.mode {
+            Mode::Move(_, text) => Line::from(vec![
+                Span::raw("Move to group (tab completes): "),
+                Span::raw(text.clone()),
+                Span::raw(" ").reversed(),
+            ]),
+            Mode::Edit(..) => Line::from("Enter saves the name, Esc cancels.").dim(),
+            Mode::Normal => Line::from(self.status.clone()),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+        frame.render_widget(Paragraph::new(Line::from(HELP).dim()), help_area);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|_| Error::CannotOpenTerminal)?;
+            if let Event::Key(key) = event::read().map_err(|_| Error::CannotOpenTerminal)? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Opens the full-screen interface (`todo --tui`).
+pub fn run_tui() -> Result<(), Error> {
+    if !std::io::stdout().is_terminal() {
+        return Err(Error::CannotOpenTerminal);
+    }
+    let mut app = App::new(read_model()?);
+    let mut terminal = ratatui::try_init().map_err(|_| Error::CannotOpenTerminal)?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}